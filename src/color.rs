@@ -12,6 +12,23 @@ pub(crate) const COLOR_CYAN: &str = "\x1b[36m";
 pub(crate) const COLOR_GRAY: &str = "\x1b[37m";
 pub(crate) const COLOR_RESET: &str = "\x1b[0m";
 
+/// Colors to pick from when coloring log targets. Excludes gray, since that is used for the rest of
+/// the source location.
+const TARGET_COLORS: [&str; 12] = [
+    "\x1b[31m", // Red
+    "\x1b[32m", // Green
+    "\x1b[33m", // Yellow
+    "\x1b[34m", // Blue
+    "\x1b[35m", // Magenta
+    "\x1b[36m", // Cyan
+    "\x1b[91m", // Bright red
+    "\x1b[92m", // Bright green
+    "\x1b[93m", // Bright yellow
+    "\x1b[94m", // Bright blue
+    "\x1b[95m", // Bright magenta
+    "\x1b[96m", // Bright cyan
+];
+
 pub(crate) trait ColorWriter {
     fn set_color(&mut self, color: &'static str) -> fmt::Result;
     fn reset_color(&mut self) -> fmt::Result;
@@ -41,3 +58,105 @@ impl ColorWriter for Writer<'_> {
         }
     }
 }
+
+/// Starts an [OSC 8 hyperlink](https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda)
+/// to the given URL. Text written after this is clickable in terminals that support it, until
+/// [`write_hyperlink_end`] is called. Terminals that don't support OSC 8 just display the text.
+pub(crate) fn write_hyperlink_start(writer: &mut Writer<'_>, url: &str) -> fmt::Result {
+    write!(writer, "\x1b]8;;{url}\x1b\\")
+}
+
+pub(crate) fn write_hyperlink_end(writer: &mut Writer<'_>) -> fmt::Result {
+    writer.write_str("\x1b]8;;\x1b\\")
+}
+
+/// Returns the number of characters in the given string that take up space in the terminal, i.e.
+/// excluding ANSI escape codes.
+pub(crate) fn visible_width(string: &str) -> usize {
+    visible_chars(string).count()
+}
+
+/// Removes ANSI escape codes (colors and hyperlinks) from the given string.
+pub(crate) fn strip_ansi_codes(string: &str) -> String {
+    visible_chars(string).collect()
+}
+
+/// Returns the characters of the given string, skipping ANSI escape codes.
+fn visible_chars(string: &str) -> impl Iterator<Item = char> + '_ {
+    let mut chars = string.chars().peekable();
+
+    std::iter::from_fn(move || loop {
+        let char = chars.next()?;
+        if char != '\x1b' {
+            return Some(char);
+        }
+
+        if chars.next_if_eq(&']').is_some() {
+            // Operating system commands (such as hyperlinks) on the form "\x1b]...\x1b\\" end
+            // with the string terminator "\x1b\\" (or the BEL character)
+            while let Some(escape_char) = chars.next() {
+                if escape_char == '\x07'
+                    || (escape_char == '\x1b' && chars.next_if_eq(&'\\').is_some())
+                {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        // Escape codes on the form "\x1b[...m" end with an ASCII letter
+        for escape_char in chars.by_ref() {
+            if escape_char.is_ascii_alphabetic() {
+                break;
+            }
+        }
+    })
+}
+
+/// Picks a color for the given log target. We implement the hash function ourselves (FNV-1a)
+/// instead of using [`std::hash::DefaultHasher`], since the standard library's hash output may
+/// change between Rust versions, and we want targets to keep their colors.
+pub(crate) fn target_color(target: &str) -> &'static str {
+    let hash = target.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    TARGET_COLORS[(hash % TARGET_COLORS.len() as u64) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{strip_ansi_codes, target_color};
+
+    #[test]
+    fn colors_are_stripped() {
+        assert_eq!(
+            strip_ansi_codes("\x1b[1;31mERROR\x1b[0m: \x1b[2mfailed\x1b[0m"),
+            "ERROR: failed"
+        );
+    }
+
+    #[test]
+    fn hyperlinks_are_stripped_with_either_terminator() {
+        assert_eq!(
+            strip_ansi_codes("\x1b]8;;file://src/lib.rs\x1b\\lib.rs\x1b]8;;\x1b\\"),
+            "lib.rs"
+        );
+        assert_eq!(
+            strip_ansi_codes("\x1b]8;;file://src/lib.rs\x07lib.rs\x1b]8;;\x07"),
+            "lib.rs"
+        );
+    }
+
+    #[test]
+    fn text_without_escape_codes_is_unchanged() {
+        assert_eq!(strip_ansi_codes("größe [31m] ok"), "größe [31m] ok");
+    }
+
+    #[test]
+    fn target_colors_are_stable() {
+        // Pinned, so that targets keep their colors across runs and Rust versions
+        assert_eq!(target_color("app::db"), "\x1b[91m");
+        assert_eq!(target_color("app::http"), "\x1b[31m");
+        assert_eq!(target_color("app::db"), target_color("app::db"));
+    }
+}
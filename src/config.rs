@@ -0,0 +1,457 @@
+use core::fmt;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{de, Deserialize, Deserializer};
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::{
+    config_watcher::ConfigFile, DevLogLayout, DevLogSubscriberBuilder, DevLogTimeFormat,
+    LevelLabels, LevelSet, OutputFormat, SourcePathStyle, TargetColoring,
+};
+
+/// Subscriber settings that can be shared between binaries, loaded from a TOML file with
+/// [`DevLogSubscriberBuilder::from_config_file`]. All settings are optional, and unset settings
+/// keep their defaults. The struct implements [`Deserialize`], so it can also be embedded in an
+/// application's own config and applied with [`DevLogSubscriberBuilder::with_config`].
+///
+/// ### Example
+///
+/// ```toml
+/// level = "info"
+/// layout = "adaptive"
+/// time = "elapsed"
+/// source = true
+///
+/// [filters]
+/// "app::db" = "debug"
+/// hyper = "warn"
+///
+/// [fields]
+/// pinned = ["request_id", "user_id"]
+/// redacted = ["password", "token"]
+///
+/// [theme]
+/// level_labels = "icons"
+/// target_coloring = "per_top_level_module"
+/// source_path_style = "short"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DevLogConfig {
+    /// The most verbose level of log events to display (`trace`, `debug`, `info`, `warn`, `error`
+    /// or `off`). See [`DevLogSubscriberBuilder::with_max_level`].
+    #[serde(deserialize_with = "deserialize_optional_level")]
+    pub level: Option<LevelFilter>,
+    /// Levels for specific targets and their submodules. See
+    /// [`DevLogSubscriberBuilder::with_target_level`].
+    #[serde(deserialize_with = "deserialize_target_levels")]
+    pub filters: BTreeMap<String, LevelFilter>,
+    /// See [`DevLogSubscriberBuilder::with_output_format`].
+    pub format: Option<OutputFormat>,
+    /// See [`DevLogSubscriberBuilder::with_layout`].
+    pub layout: Option<DevLogLayout>,
+    /// How to display timestamps.
+    pub time: Option<TimestampStyle>,
+    /// Whether to use colors. If unset, colors are enabled unless the `NO_COLOR` environment
+    /// variable is set. See [`DevLogSubscriberBuilder::with_ansi`].
+    pub color: Option<bool>,
+    /// See [`DevLogSubscriberBuilder::with_source_location`].
+    pub source: Option<bool>,
+    /// See [`DevLogSubscriberBuilder::with_target`].
+    pub target: Option<bool>,
+    /// See [`DevLogSubscriberBuilder::with_spans`].
+    pub spans: Option<bool>,
+    /// See [`DevLogSubscriberBuilder::with_thread_names`].
+    pub thread_names: Option<bool>,
+    /// See [`DevLogSubscriberBuilder::with_thread_ids`].
+    pub thread_ids: Option<bool>,
+    /// See [`DevLogSubscriberBuilder::with_line_wrapping`].
+    pub wrap_lines: Option<bool>,
+    pub fields: FieldsConfig,
+    pub theme: ThemeConfig,
+}
+
+/// Settings for which log fields are displayed, and in which order. Part of [`DevLogConfig`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FieldsConfig {
+    /// See [`DevLogSubscriberBuilder::with_pinned_fields`].
+    pub pinned: Option<Vec<String>>,
+    /// See [`DevLogSubscriberBuilder::with_sorted_fields`].
+    pub sorted: Option<bool>,
+    /// See [`DevLogSubscriberBuilder::with_hidden_fields`].
+    pub hidden: Option<Vec<String>>,
+    /// See [`DevLogSubscriberBuilder::with_redacted_fields`].
+    pub redacted: Option<Vec<String>>,
+}
+
+/// Settings for the look of the devlog output format. Part of [`DevLogConfig`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    /// Either `"names"`, `"icons"`, `"icons_only"`, or a table of custom labels (like
+    /// `{ warn = "WARNING" }`). See [`DevLogSubscriberBuilder::with_level_labels`].
+    #[serde(deserialize_with = "deserialize_level_labels")]
+    pub level_labels: Option<LevelLabels>,
+    /// See [`DevLogSubscriberBuilder::with_padded_levels`].
+    pub padded_levels: Option<bool>,
+    /// See [`DevLogSubscriberBuilder::with_aligned_columns`].
+    pub aligned_columns: Option<bool>,
+    /// See [`DevLogSubscriberBuilder::with_target_column`].
+    pub target_column: Option<usize>,
+    /// See [`DevLogSubscriberBuilder::with_target_coloring`].
+    pub target_coloring: Option<TargetColoring>,
+    /// See [`DevLogSubscriberBuilder::with_source_path_style`].
+    pub source_path_style: Option<SourcePathStyle>,
+    /// See [`DevLogSubscriberBuilder::with_source_links`].
+    pub source_links: Option<String>,
+}
+
+/// How timestamps are displayed, as configured by [`DevLogConfig::time`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampStyle {
+    /// Wall-clock time, like `[17:51:18]`. See [`DevLogTimeFormat::clock`].
+    Clock,
+    /// Time since the subscriber was created, like `[12.345s]`. See
+    /// [`DevLogTimeFormat::elapsed`].
+    Elapsed,
+    /// No timestamps.
+    #[serde(rename = "none")]
+    Hidden,
+}
+
+/// Error returned by [`DevLogSubscriberBuilder::from_config_file`] when the config file could not
+/// be read or parsed.
+#[derive(Debug)]
+pub struct ConfigError {
+    path: PathBuf,
+    kind: ConfigErrorKind,
+}
+
+#[derive(Debug)]
+enum ConfigErrorKind {
+    Read(io::Error),
+    Parse(toml::de::Error),
+}
+
+impl ConfigError {
+    /// The path of the config file that failed to load.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ConfigErrorKind::Read(_) => {
+                write!(
+                    f,
+                    "Failed to read log config file '{}'",
+                    self.path.display()
+                )
+            }
+            ConfigErrorKind::Parse(_) => {
+                write!(f, "Invalid log config file '{}'", self.path.display())
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ConfigErrorKind::Read(error) => Some(error),
+            ConfigErrorKind::Parse(error) => Some(error),
+        }
+    }
+}
+
+impl DevLogConfig {
+    /// Parses the config from TOML, from the top-level `[devlog]` table if there is one, or from
+    /// the whole document otherwise.
+    pub(crate) fn from_toml(toml: &str) -> Result<Self, toml::de::Error> {
+        #[derive(Deserialize)]
+        struct ConfigSection {
+            devlog: DevLogConfig,
+        }
+
+        if has_devlog_section(&toml::from_str(toml)?) {
+            // We parse the string again instead of converting the table, so that errors point to
+            // the right line
+            toml::from_str::<ConfigSection>(toml).map(|section| section.devlog)
+        } else {
+            toml::from_str(toml)
+        }
+    }
+
+    /// Parses the TOML table that the config is read from (see [`DevLogConfig::from_toml`]),
+    /// without checking the settings.
+    pub(crate) fn toml_table(toml: &str) -> Result<toml::Table, toml::de::Error> {
+        let mut document: toml::Table = toml::from_str(toml)?;
+        if has_devlog_section(&document) {
+            if let Some(toml::Value::Table(section)) = document.remove("devlog") {
+                return Ok(section);
+            }
+        }
+        Ok(document)
+    }
+}
+
+fn has_devlog_section(document: &toml::Table) -> bool {
+    document.contains_key("devlog")
+}
+
+impl DevLogSubscriberBuilder<DevLogTimeFormat> {
+    /// Creates a subscriber builder configured from the TOML file at the given path (e.g.
+    /// `devlog.toml`), so that every binary in a workspace can share one logging style. See
+    /// [`DevLogConfig`] for the supported settings. If the file has a top-level `[devlog]` table,
+    /// the settings are read from that, so that they can live in a section of an existing config
+    /// file. Otherwise, the whole file is read as the config.
+    ///
+    /// Call [`with_env_config`](Self::with_env_config) on the returned builder to let `DEVLOG_*`
+    /// environment variables override the file.
+    ///
+    /// ```no_run
+    /// devlog_tracing::DevLogSubscriberBuilder::from_config_file("devlog.toml")
+    ///     .expect("Failed to load log config")
+    ///     .with_env_config()
+    ///     .init();
+    /// ```
+    pub fn from_config_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let config_error = |kind| ConfigError {
+            path: path.to_path_buf(),
+            kind,
+        };
+
+        let contents =
+            fs::read_to_string(path).map_err(|error| config_error(ConfigErrorKind::Read(error)))?;
+        let config = DevLogConfig::from_toml(&contents)
+            .map_err(|error| config_error(ConfigErrorKind::Parse(error)))?;
+
+        let mut builder = Self::default().with_config(&config);
+        builder.config_file = Some(ConfigFile {
+            path: path.to_path_buf(),
+            contents,
+            poll_interval: None,
+        });
+        Ok(builder)
+    }
+
+    /// Applies the settings that are set in the given config, on top of the settings configured so
+    /// far.
+    pub fn with_config(self, config: &DevLogConfig) -> Self {
+        let builder = match config.time {
+            Some(TimestampStyle::Clock) => self.with_devlog_timer(DevLogTimeFormat::clock()),
+            Some(TimestampStyle::Elapsed) => self.with_devlog_timer(DevLogTimeFormat::elapsed()),
+            Some(TimestampStyle::Hidden) | None => self,
+        };
+        builder.apply_config(config)
+    }
+}
+
+impl<TimeFormatT> DevLogSubscriberBuilder<TimeFormatT> {
+    /// Watches the config file that the builder was created from (see
+    /// [`DevLogSubscriberBuilder::from_config_file`]) once the subscriber is initialized, polling
+    /// it for changes at the given interval. When the file changes, the new filter and display
+    /// settings are applied, and an INFO event lists what changed, separated by commas:
+    /// ```text
+    /// [17:51:18] INFO: Reloaded log config from 'devlog.toml': level: "info" -> "debug"
+    /// ```
+    ///
+    /// If the new settings hide that event (e.g. `level = "warn"`), it is printed to stderr
+    /// instead, so that changes are never applied silently.
+    ///
+    /// Settings that are removed from the file keep their current values (except for
+    /// [`filters`](DevLogConfig::filters), which are removed), and switching the timer between
+    /// `clock` and `elapsed` requires a restart. If the builder applied
+    /// [`with_env_config`](Self::with_env_config), the `DEVLOG_*` environment variables are applied
+    /// again on top of the reloaded file, so they keep taking precedence. If the file becomes
+    /// invalid, a warning is logged and the current settings are kept.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// devlog_tracing::DevLogSubscriberBuilder::from_config_file("devlog.toml")
+    ///     .expect("Failed to load log config")
+    ///     .with_config_file_watching(Duration::from_secs(1))
+    ///     .init();
+    /// ```
+    pub fn with_config_file_watching(mut self, poll_interval: Duration) -> Self {
+        match &mut self.config_file {
+            Some(config_file) => config_file.poll_interval = Some(poll_interval),
+            None => self.config_warnings.push(String::from(
+                "Can't watch log config file, since the subscriber was not created from one",
+            )),
+        }
+        self
+    }
+
+    /// Applies every setting in the config except for the timer, which changes the type of the
+    /// builder. Used by [`DevLogSubscriberBuilder::with_config`] and when reloading the config
+    /// file.
+    pub(crate) fn apply_config(mut self, config: &DevLogConfig) -> Self {
+        if let Some(level) = config.level {
+            self = self.with_max_level(level);
+        }
+        for (target, level) in &config.filters {
+            self = self.with_target_level(target, *level);
+        }
+        if let Some(output_format) = config.format {
+            self = self.with_output_format(output_format);
+        }
+        if let Some(layout) = config.layout {
+            self = self.with_layout(layout);
+        }
+
+        match config.time {
+            Some(TimestampStyle::Clock | TimestampStyle::Elapsed) => {
+                self = self.with_timestamp_for(LevelSet::ALL)
+            }
+            Some(TimestampStyle::Hidden) => self = self.with_timestamp_for(LevelSet::NONE),
+            None => {}
+        }
+
+        if let Some(ansi) = config.color {
+            self = self.with_ansi(ansi);
+        }
+        if let Some(source) = config.source {
+            self = self.with_source_location(source);
+        }
+        if let Some(target) = config.target {
+            self = self.with_target(target);
+        }
+        if let Some(spans) = config.spans {
+            self = self.with_spans(spans);
+        }
+        if let Some(thread_names) = config.thread_names {
+            self = self.with_thread_names(thread_names);
+        }
+        if let Some(thread_ids) = config.thread_ids {
+            self = self.with_thread_ids(thread_ids);
+        }
+        if let Some(wrap_lines) = config.wrap_lines {
+            self = self.with_line_wrapping(wrap_lines);
+        }
+
+        let fields = &config.fields;
+        if let Some(pinned) = &fields.pinned {
+            self = self.with_pinned_fields(pinned);
+        }
+        if let Some(sorted) = fields.sorted {
+            self = self.with_sorted_fields(sorted);
+        }
+        if let Some(hidden) = &fields.hidden {
+            self = self.with_hidden_fields(hidden);
+        }
+        if let Some(redacted) = &fields.redacted {
+            self = self.with_redacted_fields(redacted);
+        }
+
+        let theme = &config.theme;
+        if let Some(level_labels) = &theme.level_labels {
+            self = self.with_level_labels(level_labels.clone());
+        }
+        if let Some(padded_levels) = theme.padded_levels {
+            self = self.with_padded_levels(padded_levels);
+        }
+        if let Some(aligned_columns) = theme.aligned_columns {
+            self = self.with_aligned_columns(aligned_columns);
+        }
+        if let Some(width) = theme.target_column {
+            self = self.with_target_column(width);
+        }
+        if let Some(target_coloring) = theme.target_coloring {
+            self = self.with_target_coloring(target_coloring);
+        }
+        if let Some(source_path_style) = theme.source_path_style {
+            self = self.with_source_path_style(source_path_style);
+        }
+        if let Some(url_template) = &theme.source_links {
+            self = self.with_source_links(url_template);
+        }
+
+        self
+    }
+}
+
+fn parse_level<E: de::Error>(level: &str) -> Result<LevelFilter, E> {
+    level.parse().map_err(|_| {
+        E::invalid_value(
+            de::Unexpected::Str(level),
+            &"trace, debug, info, warn, error or off",
+        )
+    })
+}
+
+fn deserialize_optional_level<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<LevelFilter>, D::Error> {
+    let level = String::deserialize(deserializer)?;
+    parse_level(&level).map(Some)
+}
+
+fn deserialize_target_levels<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, LevelFilter>, D::Error> {
+    BTreeMap::<String, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(target, level)| Ok((target, parse_level(&level)?)))
+        .collect()
+}
+
+fn deserialize_level_labels<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<LevelLabels>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum LevelLabelsConfig {
+        Preset(String),
+        Custom {
+            trace: Option<String>,
+            debug: Option<String>,
+            info: Option<String>,
+            warn: Option<String>,
+            error: Option<String>,
+        },
+    }
+
+    let level_labels = match LevelLabelsConfig::deserialize(deserializer)? {
+        LevelLabelsConfig::Preset(preset) => match preset.as_str() {
+            "names" => LevelLabels::default(),
+            "icons" => LevelLabels::icons(),
+            "icons_only" => LevelLabels::icons_only(),
+            _ => {
+                return Err(de::Error::invalid_value(
+                    de::Unexpected::Str(&preset),
+                    &"names, icons, icons_only or a table of labels",
+                ))
+            }
+        },
+        LevelLabelsConfig::Custom {
+            trace,
+            debug,
+            info,
+            warn,
+            error,
+        } => {
+            let default = LevelLabels::default();
+            LevelLabels {
+                trace: trace.unwrap_or(default.trace),
+                debug: debug.unwrap_or(default.debug),
+                info: info.unwrap_or(default.info),
+                warn: warn.unwrap_or(default.warn),
+                error: error.unwrap_or(default.error),
+            }
+        }
+    };
+    Ok(Some(level_labels))
+}
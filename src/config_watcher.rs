@@ -0,0 +1,279 @@
+use core::fmt;
+use std::{collections::BTreeMap, fs, mem, path::PathBuf, thread, time::Duration};
+
+use tracing::Level;
+use tracing_subscriber::fmt::time::FormatTime;
+
+use crate::{
+    config::{DevLogConfig, TimestampStyle},
+    DevLogReloadHandle, DevLogSubscriberBuilder,
+};
+
+/// The config file that a builder was created from with
+/// [`DevLogSubscriberBuilder::from_config_file`](crate::DevLogSubscriberBuilder::from_config_file),
+/// kept so that the file can be watched for changes.
+#[derive(Debug, Clone)]
+pub(crate) struct ConfigFile {
+    pub path: PathBuf,
+    /// The contents of the file when it was loaded.
+    pub contents: String,
+    /// If set, the file is polled for changes at this interval once the subscriber is initialized.
+    pub poll_interval: Option<Duration>,
+}
+
+/// Polls the config file for changes, and applies them to the subscriber.
+struct ConfigWatcher<TimeFormatT> {
+    path: PathBuf,
+    poll_interval: Duration,
+    reload_handle: DevLogReloadHandle<TimeFormatT>,
+    /// The contents of the file the last time we read it, or `None` if reading it failed.
+    contents: Option<String>,
+    /// The last config that was successfully applied.
+    config: DevLogConfig,
+    /// The settings of the last applied config, flattened to `key = value` pairs for describing
+    /// changes.
+    settings: BTreeMap<String, String>,
+}
+
+/// Spawns a background thread that watches the config file for changes. The thread stops if the
+/// subscriber is dropped.
+pub(crate) fn spawn_config_watcher<TimeFormatT>(
+    config_file: ConfigFile,
+    poll_interval: Duration,
+    reload_handle: DevLogReloadHandle<TimeFormatT>,
+) where
+    TimeFormatT: FormatTime + Clone + Send + Sync + 'static,
+{
+    // The file was parsed successfully when the builder was created, so these don't fail unless
+    // something is very wrong
+    let (Ok(config), Ok(settings)) = (
+        DevLogConfig::from_toml(&config_file.contents),
+        flattened_settings(&config_file.contents),
+    ) else {
+        return;
+    };
+
+    let watcher = ConfigWatcher {
+        path: config_file.path,
+        poll_interval,
+        reload_handle,
+        contents: Some(config_file.contents),
+        config,
+        settings,
+    };
+
+    let path = watcher.path.clone();
+    let spawn_result = thread::Builder::new()
+        .name(String::from("devlog-config-watcher"))
+        .spawn(move || watcher.run());
+    if let Err(error) = spawn_result {
+        tracing::warn!(
+            target: "devlog_tracing",
+            "Failed to start watching log config file '{}': {error}",
+            path.display(),
+        );
+    }
+}
+
+impl<TimeFormatT> ConfigWatcher<TimeFormatT>
+where
+    TimeFormatT: FormatTime + Clone + Send + Sync + 'static,
+{
+    fn run(mut self) {
+        loop {
+            thread::sleep(self.poll_interval);
+            if !self.poll() {
+                return;
+            }
+        }
+    }
+
+    /// Checks the config file for changes, and applies them if it changed. Returns false if the
+    /// subscriber has been dropped, in which case we stop watching.
+    fn poll(&mut self) -> bool {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(error) => {
+                // The file may be briefly missing while an editor saves it, so we only warn once,
+                // and keep the current settings
+                if self.contents.take().is_some() {
+                    self.notify(
+                        Level::WARN,
+                        format_args!(
+                            "Failed to read log config file '{}': {error}",
+                            self.path.display(),
+                        ),
+                    );
+                }
+                return true;
+            }
+        };
+
+        if self.contents.as_ref() == Some(&contents) {
+            return true;
+        }
+        self.contents = Some(contents.clone());
+
+        let (config, settings) = match (
+            DevLogConfig::from_toml(&contents),
+            flattened_settings(&contents),
+        ) {
+            (Ok(config), Ok(settings)) => (config, settings),
+            (Err(error), _) | (_, Err(error)) => {
+                self.notify(
+                    Level::WARN,
+                    format_args!(
+                        "Ignoring invalid log config file '{}': {}",
+                        self.path.display(),
+                        // TOML errors end with a newline, which would leave an empty line in the
+                        // log
+                        error.to_string().trim_end(),
+                    ),
+                );
+                return true;
+            }
+        };
+
+        let changes = describe_changes(&self.settings, &settings);
+        if changes.is_empty() {
+            // Only comments or formatting changed
+            return true;
+        }
+
+        let old_config = mem::replace(&mut self.config, config);
+        self.settings = settings;
+
+        let reload_result = self.reload_handle.modify(|builder| {
+            // Settings that were removed from the file keep their current values, except for
+            // target levels, which we remove so that they don't linger
+            let builder = builder
+                .without_target_levels(old_config.filters.keys())
+                .apply_config(&self.config);
+            reapply_env_config(builder)
+        });
+        if reload_result.is_err() {
+            return false;
+        }
+
+        self.notify(
+            Level::INFO,
+            format_args!(
+                "Reloaded log config from '{}': {}",
+                self.path.display(),
+                changes.join(", "),
+            ),
+        );
+
+        // The timer is part of the subscriber's type, so we can't switch between timers here
+        let uses_timer = |time: Option<TimestampStyle>| {
+            matches!(time, Some(TimestampStyle::Clock | TimestampStyle::Elapsed))
+        };
+        if old_config.time != self.config.time
+            && uses_timer(old_config.time)
+            && uses_timer(self.config.time)
+        {
+            self.notify(
+                Level::WARN,
+                format_args!(
+                    "Changing the 'time' setting between 'clock' and 'elapsed' requires a restart"
+                ),
+            );
+        }
+
+        true
+    }
+
+    /// Logs a message about the config file with target `devlog_tracing`. If the subscriber's
+    /// filter hides it (e.g. because the new config raised the level), it is printed to stderr
+    /// instead, so that the user still learns that their change was applied or rejected.
+    fn notify(&self, level: Level, message: fmt::Arguments<'_>) {
+        let displayed = self
+            .reload_handle
+            .would_display("devlog_tracing", level)
+            .unwrap_or(false);
+        if !displayed {
+            eprintln!("devlog-tracing: {message}");
+        } else if level == Level::WARN {
+            tracing::warn!(target: "devlog_tracing", "{message}");
+        } else {
+            tracing::info!(target: "devlog_tracing", "{message}");
+        }
+    }
+}
+
+/// Applies the `DEVLOG_*` environment variables again if the builder used them, so that they keep
+/// taking precedence over the reloaded config file.
+fn reapply_env_config<TimeFormatT: 'static>(
+    builder: DevLogSubscriberBuilder<TimeFormatT>,
+) -> DevLogSubscriberBuilder<TimeFormatT> {
+    if !builder.uses_env_config {
+        return builder;
+    }
+
+    // Invalid values were already reported when the subscriber was initialized
+    let warning_count = builder.config_warnings.len();
+    let mut builder = builder.with_env_config();
+    builder.config_warnings.truncate(warning_count);
+    builder
+}
+
+/// Parses the config file into `key = value` pairs, with keys of nested tables joined by `.`.
+fn flattened_settings(contents: &str) -> Result<BTreeMap<String, String>, toml::de::Error> {
+    let mut settings = BTreeMap::new();
+    flatten_table(&DevLogConfig::toml_table(contents)?, "", &mut settings);
+    Ok(settings)
+}
+
+fn flatten_table(table: &toml::Table, prefix: &str, settings: &mut BTreeMap<String, String>) {
+    for (key, value) in table {
+        let key = format!("{prefix}{key}");
+        match value {
+            toml::Value::Table(table) => flatten_table(table, &format!("{key}."), settings),
+            value => {
+                settings.insert(key, format_value(value));
+            }
+        }
+    }
+}
+
+fn format_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(string) => format!("{string:?}"),
+        toml::Value::Integer(integer) => integer.to_string(),
+        toml::Value::Float(float) => float.to_string(),
+        toml::Value::Boolean(boolean) => boolean.to_string(),
+        toml::Value::Datetime(datetime) => datetime.to_string(),
+        toml::Value::Array(array) => {
+            let items: Vec<String> = array.iter().map(format_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        toml::Value::Table(table) => {
+            let entries: Vec<String> = table
+                .iter()
+                .map(|(key, value)| format!("{key} = {}", format_value(value)))
+                .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+    }
+}
+
+/// Describes the changed settings, like `level: "info" -> "debug"`.
+fn describe_changes(
+    old_settings: &BTreeMap<String, String>,
+    new_settings: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let mut changes = Vec::new();
+    for (key, old_value) in old_settings {
+        match new_settings.get(key) {
+            Some(new_value) if new_value == old_value => {}
+            Some(new_value) => changes.push(format!("{key}: {old_value} -> {new_value}")),
+            None => changes.push(format!("{key}: {old_value} -> (unset)")),
+        }
+    }
+    for (key, new_value) in new_settings {
+        if !old_settings.contains_key(key) {
+            changes.push(format!("{key}: (unset) -> {new_value}"));
+        }
+    }
+    changes
+}
@@ -0,0 +1,169 @@
+use std::env;
+
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::{DevLogLayout, DevLogSubscriberBuilder, DevLogTimeFormat, LevelSet, OutputFormat};
+
+impl DevLogSubscriberBuilder<DevLogTimeFormat> {
+    /// Creates a subscriber builder configured from `DEVLOG_*` environment variables. See
+    /// [`DevLogSubscriberBuilder::with_env_config`] for the supported variables.
+    pub fn from_env() -> Self {
+        Self::default().with_env_config()
+    }
+}
+
+impl<TimeFormatT> DevLogSubscriberBuilder<TimeFormatT> {
+    /// Applies settings from the following environment variables, on top of the settings
+    /// configured so far. This lets you toggle settings without recompiling. Call this last in
+    /// the builder chain, so that the environment takes precedence over programmatic settings.
+    ///
+    /// - `DEVLOG_LEVEL`: `trace`, `debug`, `info`, `warn`, `error` or `off`
+    ///   ([`with_max_level`](Self::with_max_level))
+    /// - `DEVLOG_FORMAT`: `devlog`, `json` or `logfmt`
+    ///   ([`with_output_format`](Self::with_output_format))
+    /// - `DEVLOG_LAYOUT`: `expanded`, `compact` or `adaptive` ([`with_layout`](Self::with_layout))
+    /// - `DEVLOG_TIME`: `clock`, `elapsed` or `none` ([`DevLogTimeFormat`])
+    /// - `DEVLOG_COLOR`: `always`, `never` or `auto` ([`with_ansi`](Self::with_ansi))
+    /// - `DEVLOG_SOURCE`: `1` or `0` ([`with_source_location`](Self::with_source_location))
+    /// - `DEVLOG_TARGET`: `1` or `0` ([`with_target`](Self::with_target))
+    /// - `DEVLOG_SPANS`: `1` or `0` ([`with_spans`](Self::with_spans))
+    /// - `DEVLOG_THREADS`: `name`, `id`, `name,id` or `none`
+    ///   ([`with_thread_names`](Self::with_thread_names),
+    ///   [`with_thread_ids`](Self::with_thread_ids))
+    ///
+    /// Boolean variables also accept `true`/`false`, `yes`/`no` and `on`/`off`. Values are not
+    /// case-sensitive. `DEVLOG_TIME=clock` and `DEVLOG_TIME=elapsed` switch between
+    /// [`DevLogTimeFormat::clock`] and [`DevLogTimeFormat::elapsed`], unless a custom timer has
+    /// been set with [`with_timer`](Self::with_timer) or [`without_time`](Self::without_time).
+    /// Unset or empty variables are ignored. Invalid values, and timer values that can't be
+    /// applied, are logged as warnings once the subscriber is initialized.
+    ///
+    /// ### Example
+    ///
+    /// ```sh
+    /// DEVLOG_SOURCE=1 DEVLOG_THREADS=name,id DEVLOG_TIME=elapsed cargo run
+    /// ```
+    pub fn with_env_config(mut self) -> Self {
+        self.uses_env_config = true;
+
+        if let Some(value) = env_var("DEVLOG_LEVEL") {
+            match value.parse::<LevelFilter>() {
+                Ok(level) => self = self.with_max_level(level),
+                Err(_) => self.warn_invalid(
+                    "DEVLOG_LEVEL",
+                    &value,
+                    "trace, debug, info, warn, error or off",
+                ),
+            }
+        }
+
+        if let Some(value) = env_var("DEVLOG_FORMAT") {
+            match OutputFormat::from_name(&value) {
+                Some(output_format) => self = self.with_output_format(output_format),
+                None => self.warn_invalid("DEVLOG_FORMAT", &value, "devlog, json or logfmt"),
+            }
+        }
+
+        if let Some(value) = env_var("DEVLOG_LAYOUT") {
+            match value.to_ascii_lowercase().as_str() {
+                "expanded" => self = self.with_layout(DevLogLayout::Expanded),
+                "compact" => self = self.with_layout(DevLogLayout::Compact),
+                "adaptive" => self = self.with_layout(DevLogLayout::Adaptive),
+                _ => self.warn_invalid("DEVLOG_LAYOUT", &value, "expanded, compact or adaptive"),
+            }
+        }
+
+        if let Some(value) = env_var("DEVLOG_TIME") {
+            match value.to_ascii_lowercase().as_str() {
+                "clock" => self.set_env_time(&value, DevLogTimeFormat::clock()),
+                "elapsed" => self.set_env_time(&value, DevLogTimeFormat::elapsed()),
+                "none" | "off" | "0" => self = self.with_timestamp_for(LevelSet::NONE),
+                _ => self.warn_invalid("DEVLOG_TIME", &value, "clock, elapsed or none"),
+            }
+        }
+
+        if let Some(value) = env_var("DEVLOG_COLOR") {
+            match value.to_ascii_lowercase().as_str() {
+                "always" => self = self.with_ansi(true),
+                "never" => self = self.with_ansi(false),
+                "auto" => self.ansi = None,
+                _ => self.warn_invalid("DEVLOG_COLOR", &value, "always, never or auto"),
+            }
+        }
+
+        if let Some(value) = self.env_bool("DEVLOG_SOURCE") {
+            self = self.with_source_location(value);
+        }
+        if let Some(value) = self.env_bool("DEVLOG_TARGET") {
+            self = self.with_target(value);
+        }
+        if let Some(value) = self.env_bool("DEVLOG_SPANS") {
+            self = self.with_spans(value);
+        }
+
+        if let Some(value) = env_var("DEVLOG_THREADS") {
+            let mut thread_names = false;
+            let mut thread_ids = false;
+            let mut valid = true;
+
+            for item in value.split(',').map(str::trim) {
+                match item.to_ascii_lowercase().as_str() {
+                    "name" | "names" => thread_names = true,
+                    "id" | "ids" => thread_ids = true,
+                    "none" | "" => {}
+                    _ => valid = false,
+                }
+            }
+
+            if valid {
+                self = self
+                    .with_thread_names(thread_names)
+                    .with_thread_ids(thread_ids);
+            } else {
+                self.warn_invalid("DEVLOG_THREADS", &value, "name, id, name,id or none");
+            }
+        }
+
+        self
+    }
+}
+
+impl<TimeFormatT> DevLogSubscriberBuilder<TimeFormatT> {
+    /// Parses a boolean environment variable, warning if the value is invalid.
+    fn env_bool(&mut self, name: &str) -> Option<bool> {
+        let value = env_var(name)?;
+        match value.to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Some(true),
+            "0" | "false" | "no" | "off" => Some(false),
+            _ => {
+                self.warn_invalid(name, &value, "1, 0, true, false, yes, no, on or off");
+                None
+            }
+        }
+    }
+
+    fn set_env_time(&mut self, value: &str, timer: DevLogTimeFormat) {
+        if !self.set_env_timer(timer) {
+            self.config_warnings.push(format!(
+                "Ignoring value '{value}' for DEVLOG_TIME, since a custom timer is set"
+            ));
+        }
+    }
+
+    fn warn_invalid(&mut self, name: &str, value: &str, expected: &str) {
+        self.config_warnings.push(format!(
+            "Ignoring invalid value '{value}' for {name} (expected {expected})"
+        ));
+    }
+}
+
+/// Returns the trimmed value of the environment variable, or `None` if it is unset or empty.
+fn env_var(name: &str) -> Option<String> {
+    let value = env::var(name).ok()?;
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
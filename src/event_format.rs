@@ -1,31 +1,206 @@
 use core::fmt;
-use std::thread;
+use std::{
+    cell::Cell,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{
+        atomic::{self, AtomicUsize},
+        Arc,
+    },
+    thread,
+};
 
 use crate::{
-    color::{ColorWriter, COLOR_CYAN},
-    field_format::DevLogFieldFormat,
+    color::{
+        target_color, visible_width, write_hyperlink_end, write_hyperlink_start, ColorWriter,
+        COLOR_CYAN,
+    },
+    field_format::{DevLogFieldFormat, LineWrapping},
+    flight_recorder::FlightRecorder,
+    level::{LevelLabels, LevelSet},
+    rate_limit::RateLimiter,
+    repetition::{RepeatReport, RepetitionFolder},
+    source_location::{self, SourcePathStyle},
+    span_fields::SpanFields,
+    summary::EventSummary,
+    terminal,
     time_format::DevLogTimeFormat,
 };
 
 use super::color::{COLOR_BLUE, COLOR_GRAY, COLOR_GREEN, COLOR_MAGENTA, COLOR_RED, COLOR_YELLOW};
-use tracing::{Event, Level, Metadata};
+use tracing::{callsite::Identifier, span, Event, Level, Metadata};
 use tracing_core::subscriber::Subscriber;
 use tracing_subscriber::{
     field::VisitOutput,
-    fmt::{format::Writer, time::FormatTime, FmtContext, FormatEvent, FormattedFields},
+    filter::{LevelFilter, Targets},
+    fmt::{format::Writer, time::FormatTime, FmtContext, FormatEvent},
     registry::LookupSpan,
 };
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+/// Whether to color the targets of log events, to make it easier to tell apart logs from different
+/// modules.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TargetColoring {
+    /// Displays targets in gray, like the rest of the source location. This is the default.
+    #[default]
+    Disabled,
+    /// Gives each target its own color, picked from a palette based on a hash of the target name.
+    /// The same target always gets the same color.
+    PerTarget,
+    /// Colors targets by their top-level module, i.e. the crate name and the first module after it.
+    /// For example, `app::db` and `app::db::pool` get the same color, while `app::http` gets a
+    /// different one.
+    PerTopLevelModule,
+}
+
+impl TargetColoring {
+    /// Returns the part of the target that the color should be picked from, or `None` if targets
+    /// should not be colored.
+    fn color_key<'a>(&self, target: &'a str) -> Option<&'a str> {
+        match self {
+            TargetColoring::Disabled => None,
+            TargetColoring::PerTarget => Some(target),
+            TargetColoring::PerTopLevelModule => {
+                let top_level_module = target
+                    .match_indices("::")
+                    .nth(1)
+                    .map_or(target, |(index, _)| &target[..index]);
+                Some(top_level_module)
+            }
+        }
+    }
+}
+
+/// The format that log events are written in.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum OutputFormat {
+    /// The human-readable devlog format. This is the default.
+    #[default]
+    #[cfg_attr(feature = "config", serde(rename = "devlog"))]
+    DevLog,
+    /// Newline-delimited JSON, for logs that are read by machines, e.g. in production:
+    /// ```text
+    /// {"timestamp":"2024-07-14T15:51:18.204Z","level":"INFO","target":"app::server","fields":{"message":"Server started","port":8000}}
+    /// ```
+    /// Errors with causes are written as arrays of error messages. Spans are written as nested
+    /// objects under `span`, from the root span to the current span, with each span's child span
+    /// under its own `span` member (`"span":{"name":"request","id":7,"span":{"name":"query"}}`).
+    /// Timestamps are always written in RFC 3339 format, regardless of the timer. Development
+    /// features (folding, rate limiting, the flight recorder and the summary) only apply to the
+    /// devlog format.
+    Json,
+    /// [logfmt](https://brandur.org/logfmt) key-value pairs, one log event per line:
+    /// ```text
+    /// time=2024-07-14T15:51:18.204Z level=info target=app::server msg="Server started" port=8000
+    /// ```
+    /// Errors with causes are joined into a single value (`error="outer: inner"`). The names of
+    /// the event's spans are written as `span=root>current`, followed by the fields of the spans.
+    /// As with [`OutputFormat::Json`], timestamps are in RFC 3339 format, and development features
+    /// only apply to the devlog format.
+    Logfmt,
+}
+
+impl OutputFormat {
+    /// Parses the name of an output format (`devlog`, `json` or `logfmt`), ignoring case.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "devlog" => Some(OutputFormat::DevLog),
+            "json" => Some(OutputFormat::Json),
+            "logfmt" => Some(OutputFormat::Logfmt),
+            _ => None,
+        }
+    }
+}
+
+/// How the fields and metadata (source, spans, thread) of a log event are laid out.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DevLogLayout {
+    /// Puts each log field and metadata section on its own line below the log message:
+    /// ```text
+    /// [17:51:18] INFO: Server started
+    ///   port: 8000
+    ///   source: app::server
+    /// ```
+    /// This is the default layout.
+    #[default]
+    Expanded,
+    /// Puts log fields and metadata on the same line as the log message, to save vertical space
+    /// when there are many log events:
+    /// ```text
+    /// [17:51:18] INFO: Server started port=8000 [app::server]
+    /// ```
+    /// Field values that contain whitespace or `=` are quoted, so that it's clear where each field
+    /// ends (`path="/my files"`). Span fields are written the same way, after the span name
+    /// (`span=request{id=7 path="/my files"}`).
+    Compact,
+    /// Uses the [`DevLogLayout::Compact`] layout for log events that fit within the width of the
+    /// terminal, and falls back to the [`DevLogLayout::Expanded`] layout for longer events. The
+    /// terminal width is queried from the terminal that stdout is connected to, or from the
+    /// `COLUMNS` environment variable if that fails. The width is queried at most once per
+    /// second, so a resized terminal is picked up shortly after.
+    Adaptive,
+}
+
+#[derive(Debug, Clone)]
 pub(crate) struct DevLogEventFormat<TimeFormatT> {
     pub timer: TimeFormatT,
-    pub display_timestamp: bool,
-    pub display_target: bool,
+    pub output_format: OutputFormat,
+    /// The most verbose level of events and spans to display, per target. Events are filtered
+    /// before they reach the event format (see [`FilteredOutput`](crate::output::FilteredOutput)).
+    pub filter: Targets,
+    pub layout: DevLogLayout,
+    pub wrap_lines: bool,
+    pub display_timestamp: LevelSet,
+    pub display_target: LevelSet,
     pub display_level: bool,
-    pub display_thread_id: bool,
-    pub display_thread_name: bool,
-    pub display_filename: bool,
-    pub display_line_number: bool,
+    pub display_thread_id: LevelSet,
+    pub display_thread_name: LevelSet,
+    pub display_filename: LevelSet,
+    pub display_line_number: LevelSet,
+    pub display_spans: LevelSet,
+    pub source_link_template: Option<String>,
+    pub source_path_style: SourcePathStyle,
+    /// If unset, the workspace root is detected from `CARGO_MANIFEST_DIR`.
+    pub workspace_root: Option<PathBuf>,
+    pub target_coloring: TargetColoring,
+    pub level_labels: LevelLabels,
+    pub pad_levels: bool,
+    pub align_columns: bool,
+    /// If set, the target is displayed in a column of this width before the log message, instead of
+    /// in the source section.
+    pub target_column_width: Option<usize>,
+    /// The width of the widest timestamp we've seen, used when aligning columns. Shared between
+    /// clones, since they write to the same output.
+    pub timestamp_column_width: Arc<AtomicUsize>,
+    /// If set, consecutive repeats of the same log event are folded into a single line.
+    pub repetition_folder: Option<Arc<RepetitionFolder>>,
+    /// If set, log events over the rate limit of their callsite are dropped.
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// If set, warnings and errors are recorded for the summary printed at the end of the run.
+    pub summary: Option<Arc<EventSummary>>,
+    /// If set, events that don't pass `filter` are recorded (see [`FlightRecorderFormat`]), and
+    /// shown before the next error event.
+    pub flight_recorder: Option<Arc<FlightRecorder>>,
+    /// Whether the flight recorder should only show events from the same span tree as the error.
+    pub flight_recorder_span_tree_only: bool,
+    /// A devlog timer chosen by the `DEVLOG_TIME` environment variable, which replaces `timer`.
+    /// Kept separately, since the timer can't be replaced without changing the type parameter.
+    pub env_timer: Option<DevLogTimeFormat>,
 }
 
 impl Default for DevLogEventFormat<DevLogTimeFormat> {
@@ -38,13 +213,33 @@ impl DevLogEventFormat<DevLogTimeFormat> {
     pub fn new() -> Self {
         Self {
             timer: DevLogTimeFormat::default(),
-            display_timestamp: true,
-            display_target: true,
+            output_format: OutputFormat::DevLog,
+            filter: Targets::new().with_default(LevelFilter::INFO),
+            layout: DevLogLayout::Expanded,
+            wrap_lines: false,
+            display_timestamp: LevelSet::ALL,
+            display_target: LevelSet::ALL,
             display_level: true,
-            display_thread_id: false,
-            display_thread_name: false,
-            display_filename: false,
-            display_line_number: false,
+            display_thread_id: LevelSet::NONE,
+            display_thread_name: LevelSet::NONE,
+            display_filename: LevelSet::NONE,
+            display_line_number: LevelSet::NONE,
+            display_spans: LevelSet::ALL,
+            source_link_template: None,
+            source_path_style: SourcePathStyle::Full,
+            workspace_root: None,
+            target_coloring: TargetColoring::Disabled,
+            level_labels: LevelLabels::default(),
+            pad_levels: false,
+            align_columns: false,
+            target_column_width: None,
+            timestamp_column_width: Arc::new(AtomicUsize::new(0)),
+            repetition_folder: None,
+            rate_limiter: None,
+            summary: None,
+            flight_recorder: None,
+            flight_recorder_span_tree_only: false,
+            env_timer: None,
         }
     }
 }
@@ -58,92 +253,453 @@ where
     fn format_event(
         &self,
         ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
-        mut writer: Writer<'_>,
+        writer: Writer<'_>,
         event: &Event<'_>,
     ) -> fmt::Result {
-        let metadata = event.metadata();
+        catch_formatting_panic(event, || self.format_devlog_event(ctx, writer, event))
+    }
+}
 
-        self.format_timestamp(&mut writer)?;
-        self.format_level(*metadata.level(), &mut writer)?;
-        self.format_fields(ctx, &mut writer, event)?;
-        self.format_target_and_source_location(metadata, &mut writer)?;
-        self.format_spans(ctx, &mut writer)?;
-        self.format_thread_info(&mut writer)?;
+/// Formats the events that the flight recorder of an output records, and keeps them in the flight
+/// recorder instead of writing them. Used for a separate layer from the output's own fmt layer, so
+/// that the output only sees the events and spans that it displays.
+#[derive(Debug, Clone)]
+pub(crate) struct FlightRecorderFormat<TimeFormatT>(pub DevLogEventFormat<TimeFormatT>);
 
-        writeln!(writer)
+impl<SubscriberT, TimeFormatT> FormatEvent<SubscriberT, DevLogFieldFormat>
+    for FlightRecorderFormat<TimeFormatT>
+where
+    SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    TimeFormatT: FormatTime,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+        _writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        catch_formatting_panic(event, || {
+            if let Some(flight_recorder) = &self.0.flight_recorder {
+                self.0.record_event(flight_recorder, ctx, event);
+            }
+            Ok(())
+        })
     }
 }
 
-impl<TimeFormatT> DevLogEventFormat<TimeFormatT> {
-    const TRACE_STR: &'static str = "TRACE";
-    const DEBUG_STR: &'static str = "DEBUG";
-    const INFO_STR: &'static str = "INFO";
-    const WARN_STR: &'static str = "WARN";
-    const ERROR_STR: &'static str = "ERROR";
-
-    fn format_timestamp(&self, writer: &mut Writer<'_>) -> fmt::Result
+impl<TimeFormatT> DevLogEventFormat<TimeFormatT>
+where
+    TimeFormatT: FormatTime,
+{
+    fn format_devlog_event<SubscriberT>(
+        &self,
+        ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result
     where
-        TimeFormatT: FormatTime,
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
     {
-        if self.display_timestamp {
-            writer.set_color(COLOR_GRAY)?;
-            if self.timer.format_time(writer).is_err() {
-                writer.write_str("<unknown time>")?;
+        match self.output_format {
+            OutputFormat::DevLog => {}
+            OutputFormat::Json => return self.format_json(ctx, &mut writer, event),
+            OutputFormat::Logfmt => return self.format_logfmt(ctx, &mut writer, event),
+        }
+
+        let level = *event.metadata().level();
+
+        // We record events for the summary before rate limiting and folding them, so that the
+        // counts in the summary are accurate
+        if let Some(summary) = &self.summary {
+            summary.record(event, writer.has_ansi_escapes(), || {
+                if self.display_timestamp.contains(Level::ERROR) {
+                    self.timestamp()
+                } else {
+                    String::new()
+                }
+            });
+        }
+
+        if let Some(rate_limiter) = &self.rate_limiter {
+            let rate_limit_result = rate_limiter.check(event, writer.has_ansi_escapes());
+            if rate_limit_result.drop {
+                return Ok(());
+            }
+            if let Some(report) = rate_limit_result.report {
+                writer.write_with_color(report, COLOR_GRAY)?;
+                writeln!(writer)?;
             }
-            writer.reset_color()?;
+        }
 
-            writer.write_char(' ')?;
+        if let Some(repetition_folder) = &self.repetition_folder {
+            let fold_result = repetition_folder.check(event, writer.has_ansi_escapes());
+            if let Some(report) = fold_result.report {
+                format_repeat_report(&report, &mut writer)?;
+            }
+            if fold_result.fold {
+                return Ok(());
+            }
+        }
+
+        if level == Level::ERROR {
+            if let Some(flight_recorder) = &self.flight_recorder {
+                self.format_recorded_events(flight_recorder, ctx, &mut writer)?;
+            }
+        }
+
+        self.format_with_configured_layout(ctx, &mut writer, event)?;
+        writeln!(writer)
+    }
+
+    /// Formats the event without a trailing newline, in the configured layout.
+    fn format_with_configured_layout<SubscriberT>(
+        &self,
+        ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+        writer: &mut Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result
+    where
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        match self.layout {
+            DevLogLayout::Adaptive => self.format_adaptive(ctx, writer, event),
+            layout => self.format_with_layout(ctx, writer, event, layout),
+        }
+    }
+
+    /// Formats the event in the compact layout if it fits within the width of the terminal, and in
+    /// the expanded layout otherwise.
+    fn format_adaptive<SubscriberT>(
+        &self,
+        ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+        writer: &mut Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result
+    where
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let max_width = terminal::terminal_width().unwrap_or(terminal::DEFAULT_TERMINAL_WIDTH);
+        let mut line = SingleLineBuffer::new(max_width);
+        // This writer does not enable colors, but colors don't take up space in the terminal, so
+        // the line has the same width as when written with colors
+        let fits_on_one_line = self
+            .format_with_layout(
+                ctx,
+                &mut Writer::new(&mut line),
+                event,
+                DevLogLayout::Compact,
+            )
+            .is_ok();
+
+        if !fits_on_one_line {
+            self.format_with_layout(ctx, writer, event, DevLogLayout::Expanded)
+        } else if writer.has_ansi_escapes() {
+            // We can't enable colors on our own writer, so we have to format the line again
+            self.format_with_layout(ctx, writer, event, DevLogLayout::Compact)
+        } else {
+            writer.write_str(&line.line)
+        }
+    }
+
+    /// Formats the event without colors, and keeps it in the flight recorder.
+    fn record_event<SubscriberT>(
+        &self,
+        flight_recorder: &FlightRecorder,
+        ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+        event: &Event<'_>,
+    ) where
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let mut text = String::new();
+        if self
+            .format_with_configured_layout(ctx, &mut Writer::new(&mut text), event)
+            .is_err()
+        {
+            return;
+        }
+
+        flight_recorder.record(text, root_span_id(ctx));
+    }
+
+    /// Writes the events kept by the flight recorder in gray, to give context for an error event.
+    fn format_recorded_events<SubscriberT>(
+        &self,
+        flight_recorder: &FlightRecorder,
+        ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+        writer: &mut Writer<'_>,
+    ) -> fmt::Result
+    where
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let recorded_events = if self.flight_recorder_span_tree_only {
+            flight_recorder.take_events_in_span_tree(root_span_id(ctx).as_ref())
+        } else {
+            flight_recorder.take_events()
+        };
+
+        if recorded_events.is_empty() {
+            return Ok(());
+        }
+
+        let events = if recorded_events.len() == 1 {
+            "event"
+        } else {
+            "events"
+        };
+        writer.write_with_color(
+            format_args!(
+                "(flight recorder: {} suppressed {events} before this error)",
+                recorded_events.len()
+            ),
+            COLOR_GRAY,
+        )?;
+        writeln!(writer)?;
+
+        for recorded_event in recorded_events {
+            writer.write_with_color(recorded_event, COLOR_GRAY)?;
+            writeln!(writer)?;
         }
 
         Ok(())
     }
 
-    fn format_level(&self, level: Level, writer: &mut Writer<'_>) -> fmt::Result {
-        if self.display_level {
-            let (level_string, color) = match level {
-                Level::TRACE => (Self::TRACE_STR, COLOR_MAGENTA),
-                Level::DEBUG => (Self::DEBUG_STR, COLOR_BLUE),
-                Level::INFO => (Self::INFO_STR, COLOR_GREEN),
-                Level::WARN => (Self::WARN_STR, COLOR_YELLOW),
-                Level::ERROR => (Self::ERROR_STR, COLOR_RED),
-            };
+    /// Formats the event without a trailing newline. `layout` must not be
+    /// [`DevLogLayout::Adaptive`], since that should be resolved before calling this.
+    fn format_with_layout<SubscriberT>(
+        &self,
+        ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+        writer: &mut Writer<'_>,
+        event: &Event<'_>,
+        layout: DevLogLayout,
+    ) -> fmt::Result
+    where
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let normalized_metadata = normalized_metadata(event);
+        let metadata = normalized_metadata.as_ref().unwrap_or(event.metadata());
+        let level = *metadata.level();
 
-            writer.write_with_color(level_string, color)?;
-            writer.write_with_color(':', COLOR_GRAY)?;
-            writer.write_char(' ')?;
+        let mut message_column = self.format_timestamp(level, writer)?;
+        message_column += self.format_level(level, writer)?;
+        message_column += self.format_target_column(metadata, writer)?;
+
+        let line_wrapping = self.line_wrapping(layout, message_column);
+
+        self.format_fields(ctx, writer, event, layout, line_wrapping)?;
+        self.format_target_and_source_location(metadata, writer, layout)?;
+        if self.display_spans.contains(level) {
+            self.format_spans(ctx, writer, layout)?;
         }
+        self.format_thread_info(level, writer, layout)?;
 
         Ok(())
     }
 
+    /// Returns `None` if line wrapping is disabled, or if we failed to query the terminal width.
+    fn line_wrapping(&self, layout: DevLogLayout, message_column: usize) -> Option<LineWrapping> {
+        // Events in the compact layout are meant to stay on one line, so we don't wrap them
+        if !self.wrap_lines || layout == DevLogLayout::Compact {
+            return None;
+        }
+
+        let max_width = terminal::terminal_width()?;
+
+        Some(LineWrapping {
+            max_width,
+            message_column,
+        })
+    }
+}
+
+impl<TimeFormatT> DevLogEventFormat<TimeFormatT> {
+    /// Returns the number of columns written, so we know where the log message starts.
+    fn format_timestamp(&self, level: Level, writer: &mut Writer<'_>) -> Result<usize, fmt::Error>
+    where
+        TimeFormatT: FormatTime,
+    {
+        let display_timestamp = self.display_timestamp.contains(level);
+        // If timestamps are displayed for other levels, we still write padding when aligning
+        // columns, so that messages are aligned with the events that do have timestamps
+        let pad_timestamp = self.align_columns && !self.display_timestamp.is_empty();
+        if !display_timestamp && !pad_timestamp {
+            return Ok(0);
+        }
+
+        // We write the timestamp to a string first, so we know its width
+        let timestamp = if display_timestamp {
+            self.timestamp()
+        } else {
+            String::new()
+        };
+
+        let width = visible_width(&timestamp);
+        let column_width = if pad_timestamp {
+            // Timestamps may vary in width (e.g. when displaying elapsed time), so we use the
+            // widest one we've seen so far
+            self.timestamp_column_width
+                .fetch_max(width, atomic::Ordering::Relaxed)
+                .max(width)
+        } else {
+            width
+        };
+
+        if column_width == 0 {
+            return Ok(0);
+        }
+
+        writer.write_with_color(&timestamp, COLOR_GRAY)?;
+        let padding = column_width - width;
+        write!(writer, "{:padding$} ", "")?;
+
+        // +1 for the space after the timestamp
+        Ok(column_width + 1)
+    }
+
+    fn timestamp(&self) -> String
+    where
+        TimeFormatT: FormatTime,
+    {
+        let mut timestamp = String::new();
+        let mut writer = Writer::new(&mut timestamp);
+        let result = match &self.env_timer {
+            Some(env_timer) => env_timer.format_time(&mut writer),
+            None => self.timer.format_time(&mut writer),
+        };
+        if result.is_err() {
+            timestamp = String::from("<unknown time>");
+        }
+        timestamp
+    }
+
+    /// Returns the number of columns written, so we know where the log message starts.
+    fn format_level(&self, level: Level, writer: &mut Writer<'_>) -> Result<usize, fmt::Error> {
+        if !self.display_level {
+            return Ok(0);
+        }
+
+        let color = match level {
+            Level::TRACE => COLOR_MAGENTA,
+            Level::DEBUG => COLOR_BLUE,
+            Level::INFO => COLOR_GREEN,
+            Level::WARN => COLOR_YELLOW,
+            Level::ERROR => COLOR_RED,
+        };
+
+        let label = self.level_labels.get(level);
+        writer.write_with_color(label, color)?;
+        writer.write_with_color(':', COLOR_GRAY)?;
+        writer.write_char(' ')?;
+
+        // Pads after the colon, so that log messages are aligned
+        let label_width = self.level_label_width(level);
+        let padding = label_width - label.chars().count();
+        write!(writer, "{:padding$}", "")?;
+
+        // +2 for the ": " after the level
+        Ok(label_width + 2)
+    }
+
+    /// Writes the target of the log event in a fixed-width column, if enabled. Returns the number of
+    /// columns written, so we know where the log message starts.
+    fn format_target_column(
+        &self,
+        metadata: &Metadata<'_>,
+        writer: &mut Writer<'_>,
+    ) -> Result<usize, fmt::Error> {
+        let Some(column_width) = self.target_column_width else {
+            return Ok(0);
+        };
+
+        let target = if self.display_target.contains(*metadata.level()) {
+            metadata.target()
+        } else {
+            ""
+        };
+
+        // We pick the color from the full target, so it is the same as in the source section
+        let color = self
+            .target_coloring
+            .color_key(target)
+            .map_or(COLOR_GRAY, target_color);
+
+        // If the target is too wide, we keep the end of it, since that is the most specific part
+        let target_width = target.chars().count();
+        let displayed_target = if target_width > column_width {
+            let skip = target_width - column_width + 1;
+            let start_index = target
+                .char_indices()
+                .nth(skip)
+                .map_or(target.len(), |(index, _)| index);
+            writer.write_with_color('…', COLOR_GRAY)?;
+            &target[start_index..]
+        } else {
+            target
+        };
+        writer.write_with_color(displayed_target, color)?;
+
+        let padding = column_width.saturating_sub(target_width);
+        write!(writer, "{:padding$} ", "")?;
+
+        // +1 for the space after the column
+        Ok(column_width + 1)
+    }
+
+    /// Returns the width of the level label, including padding if enabled.
+    fn level_label_width(&self, level: Level) -> usize {
+        if self.pad_levels || self.align_columns {
+            self.level_labels.max_width()
+        } else {
+            self.level_labels.get(level).chars().count()
+        }
+    }
+
     fn format_fields<SubscriberT>(
         &self,
         ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
         writer: &mut Writer<'_>,
         event: &Event<'_>,
+        layout: DevLogLayout,
+        line_wrapping: Option<LineWrapping>,
     ) -> fmt::Result
     where
         SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
     {
-        let mut visitor = ctx.field_format().make_event_visitor(writer.by_ref());
+        let mut visitor = ctx.field_format().make_event_visitor(
+            writer.by_ref(),
+            layout,
+            line_wrapping,
+            is_log_bridge_callsite(event.metadata()),
+        );
         event.record(&mut visitor);
         visitor.finish()
     }
 
+    /// Whether the given span is displayed in the event's spans. Spans are created if any output
+    /// enables them, so we skip those that this output's filter does not enable.
+    pub(crate) fn displays_span(&self, metadata: &Metadata<'_>) -> bool {
+        self.filter
+            .would_enable(metadata.target(), metadata.level())
+    }
+
     fn format_spans<SubscriberT>(
         &self,
         ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
         writer: &mut Writer<'_>,
+        layout: DevLogLayout,
     ) -> fmt::Result
     where
         SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
     {
+        if layout == DevLogLayout::Compact {
+            return self.format_spans_compact(ctx, writer, layout);
+        }
+
         if let Some(scope) = ctx.event_scope() {
             let mut seen = false;
 
-            for span in scope {
+            for span in scope.filter(|span| self.displays_span(span.metadata())) {
                 if !seen {
-                    write_field_name(writer, "span")?;
+                    write_field_name(writer, "span", layout)?;
                 }
                 seen = true;
 
@@ -152,14 +708,15 @@ impl<TimeFormatT> DevLogEventFormat<TimeFormatT> {
                 writer.write_char(' ')?;
                 writer.write_with_color(span.metadata().name(), COLOR_CYAN)?;
 
+                let field_format = ctx.field_format();
                 let extensions = span.extensions();
-                if let Some(fields) = &extensions.get::<FormattedFields<DevLogFieldFormat>>() {
-                    if !fields.is_empty() {
+                if let Some(fields) = extensions.get::<SpanFields>() {
+                    if fields.has_visible_fields(field_format) {
                         writer.write_char(' ')?;
                         writer.write_with_color('{', COLOR_GRAY)?;
                         writer.write_char(' ')?;
 
-                        write!(writer, "{fields}")?;
+                        fields.format(field_format, writer.by_ref(), DevLogLayout::Expanded)?;
 
                         writer.write_char(' ')?;
                         writer.write_with_color('}', COLOR_GRAY)?;
@@ -175,22 +732,69 @@ impl<TimeFormatT> DevLogEventFormat<TimeFormatT> {
         Ok(())
     }
 
+    /// Writes spans on a single line, from the root span to the current span.
+    fn format_spans_compact<SubscriberT>(
+        &self,
+        ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+        writer: &mut Writer<'_>,
+        layout: DevLogLayout,
+    ) -> fmt::Result
+    where
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        if let Some(scope) = ctx.event_scope() {
+            let mut seen = false;
+
+            for span in scope
+                .from_root()
+                .filter(|span| self.displays_span(span.metadata()))
+            {
+                if seen {
+                    writer.write_char(' ')?;
+                    writer.write_with_color('>', COLOR_GRAY)?;
+                    writer.write_char(' ')?;
+                } else {
+                    write_field_name(writer, "span", layout)?;
+                }
+                seen = true;
+
+                writer.write_with_color(span.metadata().name(), COLOR_CYAN)?;
+
+                let field_format = ctx.field_format();
+                let extensions = span.extensions();
+                if let Some(fields) = extensions.get::<SpanFields>() {
+                    if fields.has_visible_fields(field_format) {
+                        writer.write_with_color('{', COLOR_GRAY)?;
+                        fields.format(field_format, writer.by_ref(), layout)?;
+                        writer.write_with_color('}', COLOR_GRAY)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn format_target_and_source_location(
         &self,
-        metadata: &Metadata<'static>,
+        metadata: &Metadata<'_>,
         writer: &mut Writer<'_>,
+        layout: DevLogLayout,
     ) -> fmt::Result {
-        let target = if self.display_target {
+        let level = *metadata.level();
+
+        // If the target is displayed in its own column, we don't repeat it here
+        let target = if self.display_target.contains(level) && self.target_column_width.is_none() {
             Some(metadata.target())
         } else {
             None
         };
-        let file_name = if self.display_filename {
+        let file_name = if self.display_filename.contains(level) {
             metadata.file()
         } else {
             None
         };
-        let line_number = if self.display_line_number {
+        let line_number = if self.display_line_number.contains(level) {
             metadata.line()
         } else {
             None
@@ -200,28 +804,35 @@ impl<TimeFormatT> DevLogEventFormat<TimeFormatT> {
             return Ok(());
         }
 
-        write_field_name(writer, "source")?;
-        writer.write_char(' ')?;
-        writer.set_color(COLOR_GRAY)?;
+        match layout {
+            DevLogLayout::Expanded | DevLogLayout::Adaptive => {
+                write_field_name(writer, "source", layout)?;
+                writer.write_char(' ')?;
+                writer.set_color(COLOR_GRAY)?;
+            }
+            DevLogLayout::Compact => {
+                writer.write_char(' ')?;
+                writer.set_color(COLOR_GRAY)?;
+                writer.write_char('[')?;
+            }
+        }
 
         match (target, file_name, line_number) {
-            (Some(target), Some(file_name), Some(line_number)) => {
-                write!(writer, "{target} ({file_name}:{line_number})")?;
-            }
-            (Some(target), Some(file_name), None) => {
-                write!(writer, "{target} ({file_name})")?;
+            (Some(target), Some(file_name), line_number) => {
+                self.format_target(target, writer)?;
+                writer.write_str(" (")?;
+                self.format_file_location(metadata, file_name, line_number, writer)?;
+                writer.write_char(')')?;
             }
             (Some(target), None, Some(line_number)) => {
-                write!(writer, "{target} ({line_number})")?;
+                self.format_target(target, writer)?;
+                write!(writer, " ({line_number})")?;
             }
             (Some(target), None, None) => {
-                writer.write_str(target)?;
-            }
-            (None, Some(file_name), Some(line_number)) => {
-                write!(writer, "{file_name}:{line_number}")?;
+                self.format_target(target, writer)?;
             }
-            (None, Some(file_name), None) => {
-                writer.write_str(file_name)?;
+            (None, Some(file_name), line_number) => {
+                self.format_file_location(metadata, file_name, line_number, writer)?;
             }
             (None, None, Some(line_number)) => {
                 write!(writer, "{line_number}")?;
@@ -229,19 +840,82 @@ impl<TimeFormatT> DevLogEventFormat<TimeFormatT> {
             (None, None, None) => {}
         }
 
+        if layout == DevLogLayout::Compact {
+            writer.write_char(']')?;
+        }
         writer.reset_color()?;
         Ok(())
     }
 
-    fn format_thread_info(&self, writer: &mut Writer<'_>) -> fmt::Result {
+    /// Expects the writer to have gray color set, and keeps that color after writing the target.
+    fn format_target(&self, target: &str, writer: &mut Writer<'_>) -> fmt::Result {
+        match self.target_coloring.color_key(target) {
+            Some(color_key) => {
+                writer.write_with_color(target, target_color(color_key))?;
+                writer.set_color(COLOR_GRAY)
+            }
+            None => writer.write_str(target),
+        }
+    }
+
+    /// Writes the file path and line number of a source location. If a source link template is
+    /// configured, this is wrapped in a hyperlink, so the user can click on it to open the file.
+    fn format_file_location(
+        &self,
+        metadata: &Metadata<'_>,
+        file_name: &str,
+        line_number: Option<u32>,
+        writer: &mut Writer<'_>,
+    ) -> fmt::Result {
+        // Hyperlinks use escape codes, so we only write them when ANSI escapes are enabled
+        let link = match &self.source_link_template {
+            Some(template) if writer.has_ansi_escapes() => {
+                // We want to link to the line even if the line number is not displayed
+                Some(source_location::source_link(
+                    template,
+                    file_name,
+                    metadata.line(),
+                    self.workspace_root.as_deref(),
+                ))
+            }
+            _ => None,
+        };
+
+        if let Some(link) = &link {
+            write_hyperlink_start(writer, link)?;
+        }
+
+        let file_name = source_location::shorten_path(
+            file_name,
+            self.source_path_style,
+            self.workspace_root.as_deref(),
+        );
+        match line_number {
+            Some(line_number) => write!(writer, "{file_name}:{line_number}")?,
+            None => writer.write_str(&file_name)?,
+        }
+
+        if link.is_some() {
+            write_hyperlink_end(writer)?;
+        }
+
+        Ok(())
+    }
+
+    fn format_thread_info(
+        &self,
+        level: Level,
+        writer: &mut Writer<'_>,
+        layout: DevLogLayout,
+    ) -> fmt::Result {
         let current_thread = thread::current();
 
-        let thread_name = if self.display_thread_name {
+        let thread_name = if self.display_thread_name.contains(level) {
             current_thread.name()
         } else {
             None
         };
-        let thread_id = if self.display_thread_id {
+        let thread_id = if self.display_thread_id.contains(level) {
             Some(current_thread.id())
         } else {
             None
@@ -251,8 +925,10 @@ impl<TimeFormatT> DevLogEventFormat<TimeFormatT> {
             return Ok(());
         }
 
-        write_field_name(writer, "thread")?;
-        writer.write_char(' ')?;
+        write_field_name(writer, "thread", layout)?;
+        if layout != DevLogLayout::Compact {
+            writer.write_char(' ')?;
+        }
         writer.set_color(COLOR_GRAY)?;
 
         match (thread_name, thread_id) {
@@ -273,10 +949,200 @@ impl<TimeFormatT> DevLogEventFormat<TimeFormatT> {
     }
 }
 
-fn write_field_name(writer: &mut Writer<'_>, field_name: &str) -> fmt::Result {
-    writer.write_str("\n  ")?;
+/// Returns the metadata of the original `log` record, if the event was converted from one by the
+/// `log` bridge. The bridge's own metadata has a generic target and source location for all
+/// records, so we want to use the original record's target, file and line instead.
+#[cfg(feature = "log")]
+pub(crate) fn normalized_metadata<'a>(event: &'a Event<'_>) -> Option<Metadata<'a>> {
+    use tracing_log::NormalizeEvent;
+    event.normalized_metadata()
+}
+
+#[cfg(not(feature = "log"))]
+pub(crate) fn normalized_metadata<'a>(_event: &'a Event<'_>) -> Option<Metadata<'a>> {
+    None
+}
+
+/// Whether the callsite belongs to the `log` bridge, which has one callsite per level for records
+/// from all targets. The bridge's callsites have fields for the original record's metadata.
+#[cfg(feature = "log")]
+pub(crate) fn is_log_bridge_callsite(metadata: &Metadata<'_>) -> bool {
+    metadata.fields().field("log.target").is_some()
+}
+
+#[cfg(not(feature = "log"))]
+pub(crate) fn is_log_bridge_callsite(_metadata: &Metadata<'_>) -> bool {
+    false
+}
+
+/// Identifies the callsite that a log event came from. All records from the `log` crate at the
+/// same level share one callsite of the `log` bridge, so they are identified by the target and
+/// source location of the original record instead (from [`normalized_metadata`]).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum CallsiteKey {
+    Callsite(Identifier),
+    Log {
+        level: Level,
+        target: String,
+        file: Option<String>,
+        line: Option<u32>,
+    },
+}
+
+impl CallsiteKey {
+    pub fn new(event: &Event<'_>, normalized_metadata: Option<&Metadata<'_>>) -> Self {
+        match normalized_metadata {
+            Some(metadata) => Self::Log {
+                level: *metadata.level(),
+                target: metadata.target().to_string(),
+                file: metadata.file().map(str::to_string),
+                line: metadata.line(),
+            },
+            None => Self::Callsite(event.metadata().callsite()),
+        }
+    }
+}
+
+/// Returns the ID of the root span of the span tree that the event was logged in, if any.
+fn root_span_id<SubscriberT>(
+    ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+) -> Option<span::Id>
+where
+    SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let root_span = ctx.event_scope()?.from_root().next()?;
+    Some(root_span.id())
+}
+
+thread_local! {
+    static FORMATTING_EVENT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether the current thread is formatting a log event. A panic while this is true comes from a
+/// field's `Display` or `Debug` implementation, so the panic hook must not log it.
+pub(crate) fn is_formatting_event() -> bool {
+    FORMATTING_EVENT.get()
+}
+
+/// Runs `format` to format the given event, and discards the event if a field's `Display` or
+/// `Debug` implementation panics. Otherwise the fmt layer would keep the partially formatted event
+/// in its buffer and write it before the next event. The dropped event is reported to stderr, in
+/// addition to the panic itself, which is reported by the previous panic hook.
+fn catch_formatting_panic(event: &Event<'_>, format: impl FnOnce() -> fmt::Result) -> fmt::Result {
+    let _formatting_event = FormattingEventGuard::enter();
+
+    panic::catch_unwind(AssertUnwindSafe(format)).unwrap_or_else(|_| {
+        let metadata = event.metadata();
+        eprintln!(
+            "devlog-tracing: Dropped {} event from '{}' at {}:{}, because formatting it panicked",
+            metadata.level(),
+            metadata.target(),
+            metadata.file().unwrap_or("<unknown>"),
+            metadata.line().unwrap_or(0),
+        );
+        Err(fmt::Error)
+    })
+}
+
+/// Marks the current thread as formatting a log event until dropped (including when unwinding).
+struct FormattingEventGuard {
+    was_formatting: bool,
+}
+
+impl FormattingEventGuard {
+    fn enter() -> Self {
+        Self {
+            was_formatting: FORMATTING_EVENT.replace(true),
+        }
+    }
+}
+
+impl Drop for FormattingEventGuard {
+    fn drop(&mut self) {
+        FORMATTING_EVENT.set(self.was_formatting);
+    }
+}
+
+/// Writes the repeat count of the previous log event on its own line, indented to show that it
+/// belongs to the previous event.
+fn format_repeat_report(report: &RepeatReport, writer: &mut Writer<'_>) -> fmt::Result {
+    writer.write_str("  ")?;
+    writer.write_with_color(report, COLOR_GRAY)?;
+    writeln!(writer)
+}
+
+/// Buffers the compact form of an event in [`DevLogLayout::Adaptive`], failing as soon as the line
+/// gets wider than `max_width` or contains a line break. This way, we don't format all of a long
+/// event just to find out that it doesn't fit.
+struct SingleLineBuffer {
+    line: String,
+    width: usize,
+    max_width: usize,
+}
+
+impl SingleLineBuffer {
+    fn new(max_width: usize) -> Self {
+        Self {
+            line: String::new(),
+            width: 0,
+            max_width,
+        }
+    }
+}
+
+impl fmt::Write for SingleLineBuffer {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        if string.contains('\n') {
+            return Err(fmt::Error);
+        }
+
+        // Escape codes are always written in one piece, so we can measure each piece by itself
+        self.width += visible_width(string);
+        if self.width > self.max_width {
+            return Err(fmt::Error);
+        }
+
+        self.line.push_str(string);
+        Ok(())
+    }
+}
+
+fn write_field_name(
+    writer: &mut Writer<'_>,
+    field_name: &str,
+    layout: DevLogLayout,
+) -> fmt::Result {
+    let (delimiter, name_suffix) = match layout {
+        DevLogLayout::Expanded | DevLogLayout::Adaptive => ("\n  ", ':'),
+        DevLogLayout::Compact => (" ", '='),
+    };
+
+    writer.write_str(delimiter)?;
     writer.set_color(COLOR_CYAN)?;
     writer.write_str(field_name)?;
-    writer.write_with_color(':', COLOR_GRAY)?;
+    writer.write_with_color(name_suffix, COLOR_GRAY)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::TargetColoring;
+
+    #[test]
+    fn targets_are_colored_by_their_top_level_module() {
+        let coloring = TargetColoring::PerTopLevelModule;
+
+        assert_eq!(coloring.color_key("app::db::pool"), Some("app::db"));
+        assert_eq!(coloring.color_key("app::db"), Some("app::db"));
+        assert_eq!(coloring.color_key("app"), Some("app"));
+    }
+
+    #[test]
+    fn targets_are_colored_by_their_full_name_or_not_at_all() {
+        assert_eq!(
+            TargetColoring::PerTarget.color_key("app::db::pool"),
+            Some("app::db::pool")
+        );
+        assert_eq!(TargetColoring::Disabled.color_key("app::db"), None);
+    }
+}
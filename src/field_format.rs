@@ -1,13 +1,19 @@
 use core::fmt;
 
 use super::color::{COLOR_CYAN, COLOR_GRAY, COLOR_RESET};
+use crate::{
+    event_format::{DevLogLayout, OutputFormat},
+    json_format::write_json_string,
+    logfmt_format::{write_logfmt_value, write_quoted},
+    terminal,
+};
 use tracing::field::{Field, Visit};
 use tracing_subscriber::{
-    field::{MakeVisitor, VisitFmt, VisitOutput},
-    fmt::format::Writer,
+    field::{RecordFields, VisitFmt, VisitOutput},
+    fmt::{format::Writer, FormatFields},
 };
 
-use std::{error::Error, fmt::Debug};
+use std::{cmp::Ordering, error::Error, fmt::Debug, sync::Arc};
 
 /// A log field formatter for `tracing`, with a prettified, newline-delimited format. This
 /// aims to improve readability over the default log field format, which appends log fields on the
@@ -17,6 +23,7 @@ use std::{error::Error, fmt::Debug};
 ///
 /// This example log:
 /// ```rust
+/// # use tracing::error;
 /// error!(reason = "Bad things", severity = "BAD", "Something went wrong");
 /// ```
 /// ...gets printed like this:
@@ -27,81 +34,308 @@ use std::{error::Error, fmt::Debug};
 /// ```
 /// If your terminal supports ASCII color codes, the log field names ("reason" and "severity") above
 /// will be colored, to distinguish them from field values.
-pub(crate) struct DevLogFieldFormat;
+#[derive(Clone, Default)]
+pub(crate) struct DevLogFieldFormat {
+    pub ordering: Arc<FieldOrdering>,
+    pub output_format: OutputFormat,
+}
 
-impl<'a> MakeVisitor<Writer<'a>> for DevLogFieldFormat {
-    type Visitor = DevLogFieldVisitor<'a>;
+/// Span fields are recorded by [`SpanFieldsLayer`](crate::span_fields::SpanFieldsLayer) and
+/// formatted by each output when an event is written, so this writes nothing. Otherwise, the fmt
+/// layers of all outputs would share one `FormattedFields<DevLogFieldFormat>` per span, formatted
+/// with the settings of whichever output saw the span first.
+impl<'writer> FormatFields<'writer> for DevLogFieldFormat {
+    fn format_fields<FieldsT: RecordFields>(
+        &self,
+        _writer: Writer<'writer>,
+        _fields: FieldsT,
+    ) -> fmt::Result {
+        Ok(())
+    }
+}
 
-    fn make_visitor(&self, writer: Writer<'a>) -> Self::Visitor {
+impl DevLogFieldFormat {
+    pub(crate) fn make_span_visitor<'a>(
+        &self,
+        writer: Writer<'a>,
+        layout: DevLogLayout,
+    ) -> DevLogFieldVisitor<'a> {
         DevLogFieldVisitor {
             mode: VisitorMode::Span,
+            layout,
             writer,
             result: Ok(()),
             first_visit: true,
+            ordering: self.ordering.clone(),
+            buffered_fields: Vec::new(),
+            line_wrapping: None,
+            output_format: self.output_format,
+            from_log_bridge: false,
         }
     }
-}
 
-impl DevLogFieldFormat {
-    pub(crate) fn make_event_visitor<'a>(&self, writer: Writer<'a>) -> DevLogFieldVisitor<'a> {
+    /// `from_log_bridge` is whether the event comes from the `log` bridge (see
+    /// [`is_log_bridge_callsite`](crate::event_format::is_log_bridge_callsite)).
+    pub(crate) fn make_event_visitor<'a>(
+        &self,
+        writer: Writer<'a>,
+        layout: DevLogLayout,
+        line_wrapping: Option<LineWrapping>,
+        from_log_bridge: bool,
+    ) -> DevLogFieldVisitor<'a> {
         DevLogFieldVisitor {
             mode: VisitorMode::Event,
+            layout,
             writer,
             result: Ok(()),
             first_visit: true,
+            ordering: self.ordering.clone(),
+            buffered_fields: Vec::new(),
+            line_wrapping,
+            output_format: self.output_format,
+            from_log_bridge,
+        }
+    }
+
+    /// Whether fields with the given name are omitted.
+    pub(crate) fn is_hidden(&self, field_name: &str) -> bool {
+        self.ordering.is_hidden(field_name)
+    }
+}
+
+/// Where to wrap long log messages and field values in log events.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LineWrapping {
+    pub max_width: usize,
+    /// The column where the log message starts (after the timestamp and level), which continuation
+    /// lines of the message are indented to.
+    pub message_column: usize,
+}
+
+/// Controls which log fields are displayed, and in which order event fields are displayed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FieldOrdering {
+    /// Event fields with these names are displayed first (after the log message), in the order
+    /// given here.
+    pub pinned_fields: Vec<String>,
+    /// Whether to sort event fields that are not pinned alphabetically by name. If false, fields
+    /// are displayed in the order they were declared at the callsite.
+    pub sort_fields: bool,
+    /// Fields with these names are omitted from both events and spans.
+    pub hidden_fields: Vec<String>,
+    /// The values of fields with these names are replaced with `[REDACTED]` in both events and
+    /// spans.
+    pub redacted_fields: Vec<String>,
+}
+
+impl FieldOrdering {
+    fn is_hidden(&self, field_name: &str) -> bool {
+        self.hidden_fields.iter().any(|hidden| hidden == field_name)
+    }
+
+    fn is_redacted(&self, field_name: &str) -> bool {
+        self.redacted_fields
+            .iter()
+            .any(|redacted| redacted == field_name)
+    }
+
+    /// If this returns false, fields can be written as they are visited, without buffering them.
+    fn reorders_fields(&self) -> bool {
+        self.sort_fields || !self.pinned_fields.is_empty()
+    }
+
+    fn pin_position(&self, field_name: &str) -> usize {
+        self.pinned_fields
+            .iter()
+            .position(|pinned| pinned == field_name)
+            .unwrap_or(usize::MAX)
+    }
+
+    fn compare(&self, field_1: &BufferedField, field_2: &BufferedField) -> Ordering {
+        let pin_ordering = self
+            .pin_position(field_1.name)
+            .cmp(&self.pin_position(field_2.name));
+
+        if self.sort_fields {
+            pin_ordering.then_with(|| field_1.name.cmp(field_2.name))
+        } else {
+            // Since we use a stable sort, equal fields keep their declaration order
+            pin_ordering
         }
     }
 }
 
 pub(crate) struct DevLogFieldVisitor<'a> {
     mode: VisitorMode,
+    /// Only used in [`VisitorMode::Event`], since span fields are always written on one line.
+    layout: DevLogLayout,
     writer: Writer<'a>,
     result: fmt::Result,
     first_visit: bool,
+    ordering: Arc<FieldOrdering>,
+    /// When fields are reordered, we have to see all fields before we can write them, so we
+    /// buffer them here until [`VisitOutput::finish`].
+    buffered_fields: Vec<BufferedField>,
+    /// Only set for events in the expanded layout.
+    line_wrapping: Option<LineWrapping>,
+    /// For output formats other than [`OutputFormat::DevLog`], all fields (including the log
+    /// message) are buffered, and written in that format in [`VisitOutput::finish`].
+    output_format: OutputFormat,
+    /// Whether the event comes from the `log` bridge, which adds the target and source location
+    /// of the original log record as `log.*` fields. We show those in the source section instead.
+    from_log_bridge: bool,
+}
+
+struct BufferedField {
+    name: &'static str,
+    value: BufferedValue,
+}
+
+enum BufferedValue {
+    Text(String),
+    /// A number or boolean, which is written without quotes in structured output formats.
+    Literal(String),
+    /// An error with one or more causes, written as a list.
+    ErrorChain(Vec<String>),
+}
+
+/// Placeholder for the values of redacted fields.
+struct Redacted;
+
+impl Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
 }
 
 impl<'a> DevLogFieldVisitor<'a> {
     fn write_field(&mut self, field: &Field, value: &dyn Debug) {
-        self.write_field_name(field);
+        // Wrapped and compact values are written as strings, so that we can check their contents
+        if self.line_wrapping.is_some() || self.is_compact() {
+            self.write_string_field(field.name(), &format!("{value:?}"));
+            return;
+        }
+
+        self.write_field_name(field.name());
         if self.result.is_err() {
             return;
         }
         self.result = write!(self.writer, " {value:?}");
     }
 
-    fn write_string_field(&mut self, field: &Field, value: &str) {
-        self.write_field_name(field);
+    fn write_string_field(&mut self, field_name: &str, value: &str) {
+        self.write_field_name(field_name);
+        if self.result.is_err() {
+            return;
+        }
+        if self.is_compact() {
+            self.write_compact_value(value);
+            return;
+        }
+
+        self.result = self.writer.write_char(' ');
+        // Field names are indented by 2 spaces, and followed by ": "
+        let value_column = 2 + field_name.chars().count() + 2;
+        self.write_value(value, value_column);
+    }
+
+    /// In the compact layout, fields are separated by spaces, so we quote values that contain
+    /// whitespace or `=` to keep field boundaries clear (e.g. `path="/my files"`).
+    fn write_compact_value(&mut self, value: &str) {
+        let needs_quotes = value.is_empty()
+            || value
+                .chars()
+                .any(|char| char.is_whitespace() || char == '=');
+        self.result = if needs_quotes {
+            write_quoted(&mut self.writer, value)
+        } else {
+            self.writer.write_str(value)
+        };
+    }
+
+    /// Writes the given message or field value, wrapping it if line wrapping is enabled.
+    /// `column` is the column that the value starts at, which continuation lines are indented to.
+    fn write_value(&mut self, value: &str, column: usize) {
         if self.result.is_err() {
             return;
         }
-        self.result = write!(self.writer, " {value}")
+
+        self.result = match self.line_wrapping {
+            Some(line_wrapping) => {
+                terminal::write_wrapped(&mut self.writer, value, column, line_wrapping.max_width)
+            }
+            None => self.writer.write_str(value),
+        };
+    }
+
+    fn write_message(&mut self, message: &str) {
+        if self.is_structured() {
+            self.buffered_fields.push(BufferedField {
+                name: "message",
+                value: BufferedValue::Text(message.to_string()),
+            });
+            return;
+        }
+
+        let column = self
+            .line_wrapping
+            .map(|line_wrapping| line_wrapping.message_column)
+            .unwrap_or_default();
+        self.write_value(message, column);
     }
 
-    fn write_field_name(&mut self, field: &Field) {
+    fn write_field_name(&mut self, field: &str) {
+        let name_suffix = if self.is_compact() { '=' } else { ':' };
         self.result = if self.writer.has_ansi_escapes() {
-            write!(self.writer, "{COLOR_CYAN}{field}{COLOR_GRAY}:{COLOR_RESET}")
+            write!(
+                self.writer,
+                "{COLOR_CYAN}{field}{COLOR_GRAY}{name_suffix}{COLOR_RESET}"
+            )
         } else {
-            write!(self.writer, "{field}:")
+            write!(self.writer, "{field}{name_suffix}")
         };
     }
 
+    fn is_hidden(&self, field_name: &str) -> bool {
+        (self.from_log_bridge && field_name.starts_with("log."))
+            || self.ordering.is_hidden(field_name)
+    }
+
+    /// Whether fields are written in a machine-readable format, rather than the devlog format.
+    fn is_structured(&self) -> bool {
+        self.output_format != OutputFormat::DevLog
+    }
+
+    fn is_compact(&self) -> bool {
+        self.layout == DevLogLayout::Compact
+    }
+
     fn write_string_list_item(&mut self, value: &str, first_item: bool) {
         if self.result.is_err() {
             return;
         }
 
         match self.mode {
-            VisitorMode::Event => {
-                let delimiter = self.mode.delimiter(self.writer.has_ansi_escapes());
+            _ if self.is_compact() => {
+                let prefix = if first_item { "[" } else { ", " };
                 self.result = if self.writer.has_ansi_escapes() {
-                    write!(
-                        self.writer,
-                        "{delimiter}  {COLOR_GRAY}-{COLOR_RESET} {value}"
-                    )
+                    write!(self.writer, "{COLOR_GRAY}{prefix}{COLOR_RESET}{value}")
                 } else {
-                    write!(self.writer, "{delimiter}  - {value}")
+                    write!(self.writer, "{prefix}{value}")
                 }
             }
+            VisitorMode::Event => {
+                let delimiter = self
+                    .mode
+                    .delimiter(self.layout, self.writer.has_ansi_escapes());
+                self.result = if self.writer.has_ansi_escapes() {
+                    write!(self.writer, "{delimiter}  {COLOR_GRAY}-{COLOR_RESET} ")
+                } else {
+                    write!(self.writer, "{delimiter}  - ")
+                };
+                // List items are indented by 4 spaces, and prefixed by "- "
+                self.write_value(value, 6);
+            }
             VisitorMode::Span => {
                 self.result = if first_item {
                     write!(self.writer, "{value}")
@@ -114,18 +348,163 @@ impl<'a> DevLogFieldVisitor<'a> {
         };
     }
 
+    fn write_error_chain(&mut self, field_name: &str, error_chain: &[String]) {
+        self.write_field_name(field_name);
+        for (index, error) in error_chain.iter().enumerate() {
+            self.write_string_list_item(error, index == 0);
+        }
+
+        if self.is_compact() && self.result.is_ok() {
+            self.result = if self.writer.has_ansi_escapes() {
+                write!(self.writer, "{COLOR_GRAY}]{COLOR_RESET}")
+            } else {
+                self.writer.write_char(']')
+            }
+        }
+    }
+
     fn delimit(&mut self) {
         if self.result.is_err() {
             return;
         }
 
-        let delimiter = self.mode.delimiter(self.writer.has_ansi_escapes());
+        let delimiter = self
+            .mode
+            .delimiter(self.layout, self.writer.has_ansi_escapes());
         self.result = self.writer().write_str(delimiter);
     }
+
+    fn should_buffer_fields(&self) -> bool {
+        self.is_structured() || (self.mode == VisitorMode::Event && self.ordering.reorders_fields())
+    }
+
+    /// Writes numbers and booleans as-is in structured output formats, and like other values
+    /// otherwise.
+    fn record_literal(&mut self, field: &Field, value: impl Debug + ToString) {
+        if !self.is_structured() {
+            self.record_debug(field, &value);
+            return;
+        }
+
+        if self.is_hidden(field.name()) {
+            return;
+        }
+        if self.ordering.is_redacted(field.name()) {
+            self.record_debug(field, &Redacted);
+            return;
+        }
+
+        self.first_visit = false;
+        self.buffered_fields.push(BufferedField {
+            name: field.name(),
+            value: BufferedValue::Literal(value.to_string()),
+        });
+    }
+
+    fn write_buffered_fields(&mut self) {
+        let mut buffered_fields = std::mem::take(&mut self.buffered_fields);
+        if self.is_structured() {
+            self.write_structured_fields(buffered_fields);
+            return;
+        }
+
+        let ordering = self.ordering.clone();
+        buffered_fields.sort_by(|field_1, field_2| ordering.compare(field_1, field_2));
+
+        for field in buffered_fields {
+            self.delimit();
+            if self.result.is_err() {
+                return;
+            }
+
+            match field.value {
+                BufferedValue::Text(value) | BufferedValue::Literal(value) => {
+                    self.write_string_field(field.name, &value)
+                }
+                BufferedValue::ErrorChain(error_chain) => {
+                    self.write_error_chain(field.name, &error_chain)
+                }
+            }
+        }
+    }
+
+    fn write_structured_fields(&mut self, fields: Vec<BufferedField>) {
+        for (index, field) in fields.into_iter().enumerate() {
+            if self.result.is_err() {
+                return;
+            }
+
+            self.result = match self.output_format {
+                // Span fields are written after the span name, so they always need a leading comma
+                OutputFormat::Json => {
+                    let needs_comma = index > 0 || self.mode == VisitorMode::Span;
+                    write_json_field(&mut self.writer, field, needs_comma)
+                }
+                OutputFormat::Logfmt => write_logfmt_field(&mut self.writer, field),
+                OutputFormat::DevLog => Ok(()),
+            };
+        }
+    }
+}
+
+fn write_json_field(
+    writer: &mut Writer<'_>,
+    field: BufferedField,
+    needs_comma: bool,
+) -> fmt::Result {
+    if needs_comma {
+        writer.write_char(',')?;
+    }
+    write_json_string(writer, field.name)?;
+    writer.write_char(':')?;
+
+    match field.value {
+        BufferedValue::Text(value) => write_json_string(writer, &value),
+        BufferedValue::Literal(value) => writer.write_str(&value),
+        BufferedValue::ErrorChain(error_chain) => {
+            writer.write_char('[')?;
+            for (index, error) in error_chain.iter().enumerate() {
+                if index > 0 {
+                    writer.write_char(',')?;
+                }
+                write_json_string(writer, error)?;
+            }
+            writer.write_char(']')
+        }
+    }
+}
+
+/// Writes the field as ` key=value`, with a leading space to separate it from what came before.
+/// Since logfmt has no lists, error chains are joined into one value, like `error="outer: inner"`.
+fn write_logfmt_field(writer: &mut Writer<'_>, field: BufferedField) -> fmt::Result {
+    // logfmt conventionally uses "msg" for the log message
+    let name = if field.name == "message" {
+        "msg"
+    } else {
+        field.name
+    };
+    write!(writer, " {name}=")?;
+
+    match field.value {
+        BufferedValue::Text(value) => write_logfmt_value(writer, &value),
+        BufferedValue::Literal(value) => writer.write_str(&value),
+        BufferedValue::ErrorChain(error_chain) => {
+            write_logfmt_value(writer, &error_chain.join(": "))
+        }
+    }
 }
 
 impl<'a> Visit for DevLogFieldVisitor<'a> {
     fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if self.is_hidden(field.name()) {
+            return;
+        }
+        let value: &dyn Debug = if self.ordering.is_redacted(field.name()) {
+            &Redacted
+        } else {
+            value
+        };
+
         // A log line may or may not contain a main log message, which will be the first field and
         // have the name "message". If we do get such a message, we don't want to delimit or write
         // field name for it.
@@ -133,6 +512,15 @@ impl<'a> Visit for DevLogFieldVisitor<'a> {
             self.first_visit = false;
         }
 
+        if (!self.first_visit || self.mode == VisitorMode::Span) && self.should_buffer_fields() {
+            self.first_visit = false;
+            self.buffered_fields.push(BufferedField {
+                name: field.name(),
+                value: BufferedValue::Text(format!("{value:?}")),
+            });
+            return;
+        }
+
         if !self.first_visit {
             self.delimit();
         }
@@ -145,6 +533,9 @@ impl<'a> Visit for DevLogFieldVisitor<'a> {
             self.first_visit = false;
 
             match self.mode {
+                VisitorMode::Event if self.line_wrapping.is_some() || self.is_structured() => {
+                    self.write_message(&format!("{value:?}"))
+                }
                 VisitorMode::Event => self.result = write!(self.writer, "{value:?}"),
                 VisitorMode::Span => self.write_field(field, value),
             }
@@ -154,6 +545,29 @@ impl<'a> Visit for DevLogFieldVisitor<'a> {
     }
 
     fn record_str(&mut self, field: &Field, value: &str) {
+        if self.is_hidden(field.name()) {
+            return;
+        }
+        if self.ordering.is_redacted(field.name()) {
+            self.record_debug(field, &Redacted);
+            return;
+        }
+
+        // Structured output formats label the log message, so we only treat the field as the log
+        // message if that is what it is
+        if self.first_visit && self.is_structured() && field.name() != "message" {
+            self.first_visit = false;
+        }
+
+        if (!self.first_visit || self.mode == VisitorMode::Span) && self.should_buffer_fields() {
+            self.first_visit = false;
+            self.buffered_fields.push(BufferedField {
+                name: field.name(),
+                value: BufferedValue::Text(value.to_string()),
+            });
+            return;
+        }
+
         if !self.first_visit {
             self.delimit();
         }
@@ -166,15 +580,52 @@ impl<'a> Visit for DevLogFieldVisitor<'a> {
             self.first_visit = false;
 
             match self.mode {
-                VisitorMode::Event => self.result = self.writer.write_str(value),
-                VisitorMode::Span => self.write_string_field(field, value),
+                VisitorMode::Event => self.write_message(value),
+                VisitorMode::Span => self.write_string_field(field.name(), value),
             }
         } else {
-            self.write_string_field(field, value)
+            self.write_string_field(field.name(), value)
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record_literal(field, value);
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.record_literal(field, value);
+    }
+
+    fn record_i128(&mut self, field: &Field, value: i128) {
+        self.record_literal(field, value);
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        self.record_literal(field, value);
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.record_literal(field, value);
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        // NaN and infinity are not valid JSON numbers, so we write them like other values
+        if value.is_finite() {
+            self.record_literal(field, value);
+        } else {
+            self.record_debug(field, &value);
         }
     }
 
     fn record_error(&mut self, field: &Field, mut error: &(dyn Error + 'static)) {
+        if self.is_hidden(field.name()) {
+            return;
+        }
+        if self.ordering.is_redacted(field.name()) {
+            self.record_debug(field, &Redacted);
+            return;
+        }
+
         // If an error is the first message, that means we haven't got a main log message (since
         // that will be the first message, called "message"). In this case, we add special case
         // handling if the field is called "cause", using the error's message as the main log
@@ -183,7 +634,7 @@ impl<'a> Visit for DevLogFieldVisitor<'a> {
             self.first_visit = false;
 
             if field.name() == "cause" {
-                self.result = self.writer().write_str(&error.to_string());
+                self.write_message(&error.to_string());
 
                 match error.source() {
                     Some(cause) => error = cause,
@@ -192,29 +643,49 @@ impl<'a> Visit for DevLogFieldVisitor<'a> {
             }
         }
 
-        self.delimit();
-        if self.result.is_err() {
+        // If the error has no cause, we just write the error string. If it has a cause, we format
+        // it as a list where each cause is a list item.
+        let value = match error.source() {
+            None => BufferedValue::Text(error.to_string()),
+            Some(mut cause) => {
+                let mut error_chain = vec![error.to_string(), cause.to_string()];
+                while let Some(next_cause) = cause.source() {
+                    error_chain.push(next_cause.to_string());
+                    cause = next_cause;
+                }
+                BufferedValue::ErrorChain(error_chain)
+            }
+        };
+
+        if self.should_buffer_fields() {
+            self.buffered_fields.push(BufferedField {
+                name: field.name(),
+                value,
+            });
             return;
         }
 
-        // If the error has no cause, we just write the error string
-        let Some(cause) = error.source() else {
-            self.write_string_field(field, &error.to_string());
+        self.delimit();
+        if self.result.is_err() {
             return;
-        };
+        }
 
-        // If the error has a cause, we format it as a list where each cause is a list item
-        self.write_field_name(field);
-        self.write_string_list_item(&error.to_string(), true);
-        self.write_string_list_item(&cause.to_string(), false);
-        while let Some(cause) = cause.source() {
-            self.write_string_list_item(&cause.to_string(), false);
+        match value {
+            BufferedValue::Text(value) | BufferedValue::Literal(value) => {
+                self.write_string_field(field.name(), &value)
+            }
+            BufferedValue::ErrorChain(error_chain) => {
+                self.write_error_chain(field.name(), &error_chain)
+            }
         }
     }
 }
 
 impl<'a> VisitOutput<fmt::Result> for DevLogFieldVisitor<'a> {
-    fn finish(self) -> fmt::Result {
+    fn finish(mut self) -> fmt::Result {
+        if !self.buffered_fields.is_empty() {
+            self.write_buffered_fields();
+        }
         self.result
     }
 }
@@ -232,9 +703,13 @@ enum VisitorMode {
 }
 
 impl VisitorMode {
-    fn delimiter(&self, color_enabled: bool) -> &'static str {
+    fn delimiter(&self, layout: DevLogLayout, color_enabled: bool) -> &'static str {
         match self {
-            VisitorMode::Event => "\n  ",
+            VisitorMode::Event => match layout {
+                DevLogLayout::Expanded | DevLogLayout::Adaptive => "\n  ",
+                DevLogLayout::Compact => " ",
+            },
+            VisitorMode::Span if layout == DevLogLayout::Compact => " ",
             VisitorMode::Span => {
                 if color_enabled {
                     // Gray color
@@ -0,0 +1,72 @@
+use std::{
+    collections::VecDeque,
+    mem,
+    sync::{Mutex, PoisonError},
+};
+
+use tracing::span;
+use tracing_subscriber::filter::LevelFilter;
+
+/// Keeps the most recent log events below the display level in memory, so that they can be shown
+/// as context when an error occurs.
+#[derive(Debug)]
+pub(crate) struct FlightRecorder {
+    capacity: usize,
+    /// The most verbose level of events to record.
+    pub level: LevelFilter,
+    events: Mutex<VecDeque<RecordedEvent>>,
+}
+
+#[derive(Debug)]
+struct RecordedEvent {
+    /// The event, formatted without colors.
+    text: String,
+    /// The ID of the root span that the event was logged in, if any.
+    root_span: Option<span::Id>,
+}
+
+impl FlightRecorder {
+    pub fn new(capacity: usize, level: LevelFilter) -> Self {
+        Self {
+            capacity,
+            level,
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn record(&self, text: String, root_span: Option<span::Id>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        if events.len() >= self.capacity {
+            events.pop_front();
+        }
+        events.push_back(RecordedEvent { text, root_span });
+    }
+
+    /// Removes and returns the recorded events, oldest first.
+    pub fn take_events(&self) -> Vec<String> {
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        events.drain(..).map(|event| event.text).collect()
+    }
+
+    /// Removes and returns the recorded events from the span tree with the given root span (or
+    /// events outside of any span, if `root_span` is `None`), oldest first. Events from other span
+    /// trees are kept.
+    pub fn take_events_in_span_tree(&self, root_span: Option<&span::Id>) -> Vec<String> {
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let mut taken_events = Vec::new();
+        events.retain_mut(|event| {
+            if event.root_span.as_ref() == root_span {
+                taken_events.push(mem::take(&mut event.text));
+                false
+            } else {
+                true
+            }
+        });
+        taken_events
+    }
+}
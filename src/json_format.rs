@@ -0,0 +1,186 @@
+use core::fmt;
+use std::thread;
+
+use tracing::Event;
+use tracing_core::subscriber::Subscriber;
+use tracing_subscriber::{
+    field::VisitOutput,
+    fmt::{format::Writer, FmtContext},
+    registry::LookupSpan,
+};
+
+use crate::{
+    event_format::{is_log_bridge_callsite, normalized_metadata, DevLogEventFormat, DevLogLayout},
+    field_format::DevLogFieldFormat,
+    span_fields::SpanFields,
+    time_format::rfc3339_timestamp,
+};
+
+impl<TimeFormatT> DevLogEventFormat<TimeFormatT> {
+    /// Writes the event as a single line of JSON:
+    /// ```text
+    /// {"timestamp":"2024-07-14T15:51:18.204Z","level":"INFO","target":"app::server","fields":{"message":"Server started","port":8000},"span":{"name":"request","id":7}}
+    /// ```
+    /// The `display_*` options decide which metadata is included, like in the devlog format.
+    pub(crate) fn format_json<SubscriberT>(
+        &self,
+        ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+        writer: &mut Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result
+    where
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let normalized_metadata = normalized_metadata(event);
+        let metadata = normalized_metadata.as_ref().unwrap_or(event.metadata());
+        let level = *metadata.level();
+
+        writer.write_char('{')?;
+
+        // The devlog timestamp format is meant for humans, so we always use RFC 3339 timestamps
+        // (in UTC) here, which log tooling can parse
+        if self.display_timestamp.contains(level) {
+            writer.write_str("\"timestamp\":")?;
+            write_json_string(writer, &rfc3339_timestamp())?;
+            writer.write_char(',')?;
+        }
+
+        write!(writer, "\"level\":\"{level}\"")?;
+
+        if self.display_target.contains(level) {
+            writer.write_str(",\"target\":")?;
+            write_json_string(writer, metadata.target())?;
+        }
+        if self.display_filename.contains(level) {
+            if let Some(file_name) = metadata.file() {
+                writer.write_str(",\"file\":")?;
+                write_json_string(writer, file_name)?;
+            }
+        }
+        if self.display_line_number.contains(level) {
+            if let Some(line_number) = metadata.line() {
+                write!(writer, ",\"line\":{line_number}")?;
+            }
+        }
+
+        let current_thread = thread::current();
+        if self.display_thread_name.contains(level) {
+            if let Some(thread_name) = current_thread.name() {
+                writer.write_str(",\"thread_name\":")?;
+                write_json_string(writer, thread_name)?;
+            }
+        }
+        if self.display_thread_id.contains(level) {
+            writer.write_str(",\"thread_id\":")?;
+            write_json_string(writer, &format!("{:?}", current_thread.id()))?;
+        }
+
+        writer.write_str(",\"fields\":{")?;
+        let mut visitor = ctx.field_format().make_event_visitor(
+            writer.by_ref(),
+            DevLogLayout::Expanded,
+            None,
+            is_log_bridge_callsite(event.metadata()),
+        );
+        event.record(&mut visitor);
+        visitor.finish()?;
+        writer.write_char('}')?;
+
+        if self.display_spans.contains(level) {
+            self.format_json_spans(ctx, writer)?;
+        }
+
+        writer.write_str("}\n")
+    }
+
+    /// Writes the spans of the event as nested objects under `span`, from the root span to the
+    /// current span, with each span's child span under its own `span` member. Span fields are
+    /// already formatted as JSON object members by the field formatter.
+    fn format_json_spans<SubscriberT>(
+        &self,
+        ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+        writer: &mut Writer<'_>,
+    ) -> fmt::Result
+    where
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let Some(scope) = ctx.event_scope() else {
+            return Ok(());
+        };
+
+        let mut depth = 0;
+        for span in scope
+            .from_root()
+            .filter(|span| self.displays_span(span.metadata()))
+        {
+            writer.write_str(",\"span\":{\"name\":")?;
+            write_json_string(writer, span.metadata().name())?;
+
+            let extensions = span.extensions();
+            if let Some(fields) = extensions.get::<SpanFields>() {
+                fields.format(ctx.field_format(), writer.by_ref(), DevLogLayout::Expanded)?;
+            }
+            depth += 1;
+        }
+
+        for _ in 0..depth {
+            writer.write_char('}')?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the given string as a JSON string literal, with quotes and escapes.
+pub(crate) fn write_json_string(writer: &mut impl fmt::Write, string: &str) -> fmt::Result {
+    writer.write_char('"')?;
+
+    let mut unescaped_start = 0;
+    for (index, char) in string.char_indices() {
+        let escape = match char {
+            '"' => Some("\\\""),
+            '\\' => Some("\\\\"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            '\t' => Some("\\t"),
+            // Other control characters don't have short escapes, so we write their code point
+            char if char.is_control() => None,
+            _ => continue,
+        };
+
+        writer.write_str(&string[unescaped_start..index])?;
+        match escape {
+            Some(escape) => writer.write_str(escape)?,
+            None => write!(writer, "\\u{:04x}", char as u32)?,
+        }
+        unescaped_start = index + char.len_utf8();
+    }
+
+    writer.write_str(&string[unescaped_start..])?;
+    writer.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_json_string;
+
+    fn json_string(string: &str) -> String {
+        let mut output = String::new();
+        write_json_string(&mut output, string).unwrap();
+        output
+    }
+
+    #[test]
+    fn json_strings_escape_quotes_backslashes_and_whitespace() {
+        assert_eq!(json_string("say \"hi\"\\\n\r\t"), r#""say \"hi\"\\\n\r\t""#);
+    }
+
+    #[test]
+    fn json_strings_escape_other_control_characters_as_code_points() {
+        assert_eq!(json_string("a\x1b[31mb\x7f"), r#""a\u001b[31mb\u007f""#);
+    }
+
+    #[test]
+    fn json_strings_keep_non_ascii_characters() {
+        assert_eq!(json_string("größe 🦀"), "\"größe 🦀\"");
+    }
+}
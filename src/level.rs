@@ -0,0 +1,165 @@
+use tracing::Level;
+
+/// A set of log levels, used to display parts of log events only for some levels. For example,
+/// you may want to show source locations only for warnings and errors:
+/// ```rust
+/// use devlog_tracing::LevelSet;
+/// use tracing::Level;
+///
+/// devlog_tracing::subscriber()
+///     .with_source_location_for(LevelSet::at_least(Level::WARN))
+///     .init();
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct LevelSet {
+    /// One bit for each level, indexed by [`severity`].
+    bits: u8,
+}
+
+impl LevelSet {
+    /// Contains every log level.
+    pub const ALL: LevelSet = LevelSet { bits: 0b11111 };
+
+    /// Contains no log levels.
+    pub const NONE: LevelSet = LevelSet { bits: 0 };
+
+    /// Returns a set of the given levels.
+    pub fn from_levels(levels: impl IntoIterator<Item = Level>) -> Self {
+        let bits = levels
+            .into_iter()
+            .fold(0, |bits, level| bits | (1 << severity(level)));
+        LevelSet { bits }
+    }
+
+    /// Returns a set of the given level and all levels that are more severe than it. For example,
+    /// `LevelSet::at_least(Level::WARN)` contains WARN and ERROR.
+    pub fn at_least(level: Level) -> Self {
+        LevelSet {
+            bits: Self::ALL.bits & !((1 << severity(level)) - 1),
+        }
+    }
+
+    /// Returns a set of the given level and all levels that are less severe than it. For example,
+    /// `LevelSet::at_most(Level::DEBUG)` contains DEBUG and TRACE.
+    pub fn at_most(level: Level) -> Self {
+        LevelSet {
+            bits: (1 << (severity(level) + 1)) - 1,
+        }
+    }
+
+    /// Returns true if the given level is in this set.
+    pub fn contains(&self, level: Level) -> bool {
+        self.bits & (1 << severity(level)) != 0
+    }
+
+    /// Returns true if this set contains no levels.
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+}
+
+/// `true` gives [`LevelSet::ALL`], and `false` gives [`LevelSet::NONE`].
+impl From<bool> for LevelSet {
+    fn from(all_levels: bool) -> Self {
+        if all_levels {
+            Self::ALL
+        } else {
+            Self::NONE
+        }
+    }
+}
+
+/// The labels that are displayed for each log level. The default labels are the level names in
+/// uppercase (`INFO`, `WARN` etc.). You can set your own labels like this:
+/// ```rust
+/// use devlog_tracing::LevelLabels;
+///
+/// devlog_tracing::subscriber()
+///     .with_level_labels(LevelLabels {
+///         warn: "WARNING".to_string(),
+///         ..LevelLabels::default()
+///     })
+///     .init();
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct LevelLabels {
+    pub trace: String,
+    pub debug: String,
+    pub info: String,
+    pub warn: String,
+    pub error: String,
+}
+
+impl Default for LevelLabels {
+    fn default() -> Self {
+        Self {
+            trace: String::from("TRACE"),
+            debug: String::from("DEBUG"),
+            info: String::from("INFO"),
+            warn: String::from("WARN"),
+            error: String::from("ERROR"),
+        }
+    }
+}
+
+impl LevelLabels {
+    /// Level names prefixed by Unicode icons, e.g. `✖ ERROR` and `⚠ WARN`. Requires a terminal font
+    /// that supports these characters.
+    pub fn icons() -> Self {
+        Self {
+            trace: String::from("· TRACE"),
+            debug: String::from("• DEBUG"),
+            info: String::from("ℹ INFO"),
+            warn: String::from("⚠ WARN"),
+            error: String::from("✖ ERROR"),
+        }
+    }
+
+    /// Only Unicode icons (`✖`, `⚠`, `ℹ` etc.), for the most compact level labels.
+    pub fn icons_only() -> Self {
+        Self {
+            trace: String::from("·"),
+            debug: String::from("•"),
+            info: String::from("ℹ"),
+            warn: String::from("⚠"),
+            error: String::from("✖"),
+        }
+    }
+
+    /// Returns the label for the given level.
+    pub fn get(&self, level: Level) -> &str {
+        match level {
+            Level::TRACE => &self.trace,
+            Level::DEBUG => &self.debug,
+            Level::INFO => &self.info,
+            Level::WARN => &self.warn,
+            Level::ERROR => &self.error,
+        }
+    }
+
+    /// Returns the width of the widest label, for aligning log messages.
+    pub(crate) fn max_width(&self) -> usize {
+        [
+            &self.trace,
+            &self.debug,
+            &self.info,
+            &self.warn,
+            &self.error,
+        ]
+        .into_iter()
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or_default()
+    }
+}
+
+/// Returns 0 for the least severe level (TRACE), and 4 for the most severe level (ERROR).
+fn severity(level: Level) -> u8 {
+    match level {
+        Level::TRACE => 0,
+        Level::DEBUG => 1,
+        Level::INFO => 2,
+        Level::WARN => 3,
+        Level::ERROR => 4,
+    }
+}
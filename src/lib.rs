@@ -1,12 +1,51 @@
+#[cfg(feature = "config")]
+pub use config::{ConfigError, DevLogConfig, FieldsConfig, ThemeConfig, TimestampStyle};
+pub use event_format::{DevLogLayout, OutputFormat, TargetColoring};
+pub use level::{LevelLabels, LevelSet};
+pub use log_file::{LogFile, Rotation};
+pub use reload_handle::DevLogReloadHandle;
+pub use source_location::SourcePathStyle;
 pub use subscriber_builder::DevLogSubscriberBuilder;
+pub use summary::SummaryGuard;
 pub use time_format::DevLogTimeFormat;
 
 mod color;
+#[cfg(feature = "config")]
+mod config;
+#[cfg(feature = "config")]
+mod config_watcher;
+mod env_config;
 mod event_format;
 mod field_format;
+mod flight_recorder;
+mod json_format;
+mod level;
+mod log_file;
+mod logfmt_format;
+mod output;
+mod panic_hook;
+mod rate_limit;
+mod reload_handle;
+mod repetition;
+mod source_location;
+mod span_fields;
 mod subscriber_builder;
+mod summary;
+mod terminal;
 mod time_format;
+mod writer;
 
 pub fn subscriber() -> DevLogSubscriberBuilder<DevLogTimeFormat> {
     DevLogSubscriberBuilder::default()
 }
+
+/// Like [`subscriber`], but picks the output format based on the environment: the human-readable
+/// devlog format when stdout is a terminal or in debug builds, and JSON otherwise. Set the
+/// `LOG_FORMAT` environment variable to `devlog`, `json` or `logfmt` to override this. See
+/// [`DevLogSubscriberBuilder::with_auto_output_format`].
+/// ```
+/// devlog_tracing::auto().init();
+/// ```
+pub fn auto() -> DevLogSubscriberBuilder<DevLogTimeFormat> {
+    DevLogSubscriberBuilder::default().with_auto_output_format()
+}
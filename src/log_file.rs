@@ -0,0 +1,408 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{self, AtomicBool},
+        Mutex, PoisonError,
+    },
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Local, NaiveDate, TimeDelta, Timelike};
+
+use crate::color::strip_ansi_codes;
+
+/// A log file that devlog output is written to in addition to the terminal, with colors stripped.
+/// Passed to [`DevLogSubscriberBuilder::with_log_file`](crate::DevLogSubscriberBuilder::with_log_file).
+///
+/// The current log file is always at the given path. When the file is rotated, it is renamed to
+/// include the time period it covers (e.g. `app.2024-07-14.log` for daily rotation), and a new file
+/// is started.
+///
+/// ### Example
+///
+/// ```
+/// use devlog_tracing::{LogFile, Rotation};
+///
+/// // Keeps today's logs in logs/app.log, and the logs of the last 7 days in
+/// // logs/app.<date>.log
+/// let log_file = LogFile::new("logs/app.log")
+///     .with_rotation(Rotation::Daily)
+///     .with_max_files(7);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LogFile {
+    path: PathBuf,
+    rotation: Rotation,
+    max_files: Option<usize>,
+}
+
+/// When to start a new log file. See [`LogFile::with_rotation`].
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Rotation {
+    /// Never rotates the log file, so it grows without bounds. This is the default.
+    #[default]
+    Never,
+    /// Rotates the log file before it would grow past the given number of bytes.
+    MaxSize(u64),
+    /// Rotates the log file at the start of every hour (in local time).
+    Hourly,
+    /// Rotates the log file at the start of every day (in local time).
+    Daily,
+}
+
+impl LogFile {
+    /// Writes logs to the file at the given path, creating the file (and its parent directories)
+    /// if it doesn't exist, and appending to it if it does.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            rotation: Rotation::Never,
+            max_files: None,
+        }
+    }
+
+    /// Sets when to start a new log file. Defaults to [`Rotation::Never`].
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Keeps at most `max_files` rotated log files (in addition to the current one), deleting the
+    /// oldest ones. By default, rotated log files are kept forever.
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = Some(max_files);
+        self
+    }
+}
+
+impl Rotation {
+    /// Returns the time period that the given time belongs to. `None` if rotation is not
+    /// time-based.
+    fn period(&self, time: DateTime<Local>) -> Option<Period> {
+        match self {
+            Rotation::Hourly => {
+                let start_of_hour = time
+                    - TimeDelta::minutes(time.minute().into())
+                    - TimeDelta::seconds(time.second().into())
+                    - TimeDelta::nanoseconds(time.nanosecond().into());
+                Some(Period {
+                    name: time.format("%Y-%m-%d_%H").to_string(),
+                    end: start_of_hour + TimeDelta::hours(1),
+                })
+            }
+            Rotation::Daily => Some(Period {
+                name: time.format("%Y-%m-%d").to_string(),
+                end: time
+                    .date_naive()
+                    .succ_opt()
+                    .and_then(start_of_day)
+                    .unwrap_or(time + TimeDelta::days(1)),
+            }),
+            Rotation::Never | Rotation::MaxSize(_) => None,
+        }
+    }
+}
+
+/// The first time on the given date in local time. That's midnight, unless midnight is skipped
+/// because daylight saving time starts then.
+fn start_of_day(date: NaiveDate) -> Option<DateTime<Local>> {
+    (0..24).find_map(|hour| {
+        date.and_hms_opt(hour, 0, 0)?
+            .and_local_timezone(Local)
+            .earliest()
+    })
+}
+
+/// A time period that a log file covers, with time-based rotation.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Period {
+    /// Included in the name of the file when it is rotated.
+    name: String,
+    /// The file is rotated on the first write at or after this time.
+    end: DateTime<Local>,
+}
+
+/// Writes log events to a [`LogFile`], rotating it as configured. Shared between clones of the
+/// subscriber's writer.
+#[derive(Debug)]
+pub(crate) struct RollingFile {
+    settings: LogFile,
+    /// The file is opened on the first write, unless opened earlier with [`RollingFile::open`].
+    current_file: Mutex<Option<CurrentFile>>,
+    /// We only report the first write error, to avoid flooding the terminal.
+    reported_error: AtomicBool,
+}
+
+#[derive(Debug)]
+struct CurrentFile {
+    file: File,
+    size: u64,
+    /// The time period that the file covers, if rotation is time-based.
+    period: Option<Period>,
+}
+
+impl RollingFile {
+    pub fn new(settings: LogFile) -> Self {
+        Self {
+            settings,
+            current_file: Mutex::new(None),
+            reported_error: AtomicBool::new(false),
+        }
+    }
+
+    /// Opens the log file, so that errors can be reported when the subscriber is initialized
+    /// instead of on the first write.
+    pub fn open(&self) -> io::Result<()> {
+        let mut current_file = self
+            .current_file
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if current_file.is_none() {
+            *current_file = Some(self.open_current_file()?);
+        }
+        Ok(())
+    }
+
+    /// Writes the formatted log event to the file, without colors. Errors are reported to stderr,
+    /// since there is nowhere else to report them, and we don't want to stop the terminal output.
+    pub fn write(&self, event: &[u8]) {
+        let result = if event.contains(&b'\x1b') {
+            let event = String::from_utf8_lossy(event);
+            self.write_stripped(strip_ansi_codes(&event).as_bytes())
+        } else {
+            self.write_stripped(event)
+        };
+
+        if let Err(error) = result {
+            if !self.reported_error.swap(true, atomic::Ordering::Relaxed) {
+                eprintln!(
+                    "devlog-tracing: Failed to write to log file '{}': {error}",
+                    self.settings.path.display()
+                );
+            }
+        }
+    }
+
+    fn write_stripped(&self, event: &[u8]) -> io::Result<()> {
+        let mut current_file = self
+            .current_file
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let file = match current_file.as_mut() {
+            Some(file) => file,
+            None => current_file.insert(self.open_current_file()?),
+        };
+
+        if self.should_rotate(file, event.len() as u64) {
+            // Closes the old file before renaming it, which is required on Windows
+            let period = file.period.take().map(|period| period.name);
+            *current_file = None;
+            self.rotate(period)?;
+            current_file.insert(self.open_current_file()?).write(event)
+        } else {
+            file.write(event)
+        }
+    }
+
+    fn should_rotate(&self, file: &CurrentFile, event_size: u64) -> bool {
+        match self.settings.rotation {
+            Rotation::Never => false,
+            // An empty file is never rotated, so that events larger than the max size are still
+            // written
+            Rotation::MaxSize(max_size) => file.size > 0 && file.size + event_size > max_size,
+            Rotation::Hourly | Rotation::Daily => file
+                .period
+                .as_ref()
+                .is_some_and(|period| Local::now() >= period.end),
+        }
+    }
+
+    fn open_current_file(&self) -> io::Result<CurrentFile> {
+        let path = &self.settings.path;
+        if let Some(directory) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(directory)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let metadata = file.metadata()?;
+
+        // If we're appending to an existing file, it covers the period it was last written in, so
+        // that it's rotated on the next write if that period is over
+        let modified = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+        Ok(CurrentFile {
+            file,
+            size: metadata.len(),
+            period: self.settings.rotation.period(modified.into()),
+        })
+    }
+
+    /// Renames the current log file to include the given period (or the current time, if rotation
+    /// is not time-based), and removes old log files if there are too many.
+    fn rotate(&self, period: Option<String>) -> io::Result<()> {
+        let suffix = period.unwrap_or_else(|| Local::now().format("%Y-%m-%d_%H-%M-%S").to_string());
+
+        let mut rotated_path = self.rotated_path(&suffix);
+        let mut counter = 1;
+        while rotated_path.exists() {
+            rotated_path = self.rotated_path(&format!("{suffix}-{counter}"));
+            counter += 1;
+        }
+        fs::rename(&self.settings.path, rotated_path)?;
+
+        if let Some(max_files) = self.settings.max_files {
+            self.remove_old_files(max_files)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the path of a rotated log file, e.g. `logs/app.2024-07-14.log` for
+    /// `logs/app.log`.
+    fn rotated_path(&self, suffix: &str) -> PathBuf {
+        let path = &self.settings.path;
+        let mut file_name = OsString::from(path.file_stem().unwrap_or_default());
+        file_name.push(".");
+        file_name.push(suffix);
+        if let Some(extension) = path.extension() {
+            file_name.push(".");
+            file_name.push(extension);
+        }
+        path.with_file_name(file_name)
+    }
+
+    fn remove_old_files(&self, max_files: usize) -> io::Result<()> {
+        let path = &self.settings.path;
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        let mut rotated_files = Vec::new();
+        for entry in fs::read_dir(directory)? {
+            let entry = entry?;
+            if self.is_rotated_file(&entry.path()) {
+                let modified = entry.metadata()?.modified()?;
+                rotated_files.push((modified, entry.path()));
+            }
+        }
+
+        if rotated_files.len() <= max_files {
+            return Ok(());
+        }
+
+        // Oldest first
+        rotated_files.sort();
+        let remove_count = rotated_files.len() - max_files;
+        for (_, path) in rotated_files.into_iter().take(remove_count) {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the given path is a log file rotated from our log file, i.e. has the same stem and
+    /// extension, with a rotation suffix from [`RollingFile::rotate`] in between. Other files with
+    /// the same stem and extension (like `app.trace.log` next to `app.log`) are not ours to delete.
+    fn is_rotated_file(&self, path: &Path) -> bool {
+        let (Some(file_name), Some(stem)) = (
+            path.file_name().and_then(|name| name.to_str()),
+            self.settings
+                .path
+                .file_stem()
+                .and_then(|stem| stem.to_str()),
+        ) else {
+            return false;
+        };
+
+        let extension = match self.settings.path.extension() {
+            Some(extension) => format!(".{}", extension.to_string_lossy()),
+            None => String::new(),
+        };
+
+        file_name
+            .strip_prefix(stem)
+            .and_then(|rest| rest.strip_prefix('.'))
+            .and_then(|rest| rest.strip_suffix(extension.as_str()))
+            .is_some_and(is_rotation_suffix)
+    }
+}
+
+/// Whether the string is a rotation timestamp, optionally followed by the `-N` counter that
+/// [`RollingFile::rotate`] adds when a rotated file with the same timestamp already exists.
+fn is_rotation_suffix(suffix: &str) -> bool {
+    if is_rotation_timestamp(suffix) {
+        return true;
+    }
+
+    suffix.rsplit_once('-').is_some_and(|(timestamp, counter)| {
+        !counter.is_empty()
+            && counter.bytes().all(|byte| byte.is_ascii_digit())
+            && is_rotation_timestamp(timestamp)
+    })
+}
+
+/// Whether the string has the shape of a timestamp that log files are rotated with: `%Y-%m-%d`
+/// (daily), `%Y-%m-%d_%H` (hourly) or `%Y-%m-%d_%H-%M-%S` (by size).
+fn is_rotation_timestamp(timestamp: &str) -> bool {
+    // Digits are represented by 0 in the patterns
+    const PATTERNS: [&str; 3] = ["0000-00-00", "0000-00-00_00", "0000-00-00_00-00-00"];
+
+    PATTERNS.iter().any(|pattern| {
+        pattern.len() == timestamp.len()
+            && pattern
+                .bytes()
+                .zip(timestamp.bytes())
+                .all(|(expected, actual)| match expected {
+                    b'0' => actual.is_ascii_digit(),
+                    expected => actual == expected,
+                })
+    })
+}
+
+impl CurrentFile {
+    fn write(&mut self, event: &[u8]) -> io::Result<()> {
+        self.file.write_all(event)?;
+        self.size += event.len() as u64;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use super::Rotation;
+
+    #[test]
+    fn hourly_periods_end_at_the_start_of_the_next_hour() {
+        let time = Local.with_ymd_and_hms(2024, 7, 14, 17, 51, 18).unwrap();
+        let period = Rotation::Hourly.period(time).unwrap();
+        assert_eq!(period.name, "2024-07-14_17");
+        assert_eq!(
+            period.end,
+            Local.with_ymd_and_hms(2024, 7, 14, 18, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn daily_periods_end_at_the_start_of_the_next_day() {
+        let time = Local.with_ymd_and_hms(2024, 12, 31, 17, 51, 18).unwrap();
+        let period = Rotation::Daily.period(time).unwrap();
+        assert_eq!(period.name, "2024-12-31");
+        assert_eq!(
+            period.end,
+            Local.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn size_based_rotation_has_no_periods() {
+        let time = Local.with_ymd_and_hms(2024, 7, 14, 17, 51, 18).unwrap();
+        assert_eq!(Rotation::MaxSize(1024).period(time), None);
+    }
+}
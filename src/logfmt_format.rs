@@ -0,0 +1,193 @@
+use core::fmt;
+use std::thread;
+
+use tracing::Event;
+use tracing_core::subscriber::Subscriber;
+use tracing_subscriber::{
+    field::VisitOutput,
+    fmt::{format::Writer, FmtContext},
+    registry::LookupSpan,
+};
+
+use crate::{
+    event_format::{is_log_bridge_callsite, normalized_metadata, DevLogEventFormat, DevLogLayout},
+    field_format::DevLogFieldFormat,
+    span_fields::SpanFields,
+    time_format::rfc3339_timestamp,
+};
+
+impl<TimeFormatT> DevLogEventFormat<TimeFormatT> {
+    /// Writes the event as a single line of logfmt:
+    /// ```text
+    /// time=2024-07-14T15:51:18.204Z level=info target=app::server msg="Server started" port=8000 span=request id=7
+    /// ```
+    /// The `display_*` options decide which metadata is included, like in the devlog format.
+    pub(crate) fn format_logfmt<SubscriberT>(
+        &self,
+        ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+        writer: &mut Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result
+    where
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let normalized_metadata = normalized_metadata(event);
+        let metadata = normalized_metadata.as_ref().unwrap_or(event.metadata());
+        let level = *metadata.level();
+
+        if self.display_timestamp.contains(level) {
+            write!(writer, "time={} ", rfc3339_timestamp())?;
+        }
+
+        write!(writer, "level={}", level.as_str().to_ascii_lowercase())?;
+
+        if self.display_target.contains(level) {
+            writer.write_str(" target=")?;
+            write_logfmt_value(writer, metadata.target())?;
+        }
+
+        // Field names are written by the field formatter, which puts a space before each field
+        let mut visitor = ctx.field_format().make_event_visitor(
+            writer.by_ref(),
+            DevLogLayout::Expanded,
+            None,
+            is_log_bridge_callsite(event.metadata()),
+        );
+        event.record(&mut visitor);
+        visitor.finish()?;
+
+        if self.display_spans.contains(level) {
+            self.format_logfmt_spans(ctx, writer)?;
+        }
+
+        if self.display_filename.contains(level) {
+            if let Some(file_name) = metadata.file() {
+                writer.write_str(" file=")?;
+                write_logfmt_value(writer, file_name)?;
+            }
+        }
+        if self.display_line_number.contains(level) {
+            if let Some(line_number) = metadata.line() {
+                write!(writer, " line={line_number}")?;
+            }
+        }
+
+        let current_thread = thread::current();
+        if self.display_thread_name.contains(level) {
+            if let Some(thread_name) = current_thread.name() {
+                writer.write_str(" thread=")?;
+                write_logfmt_value(writer, thread_name)?;
+            }
+        }
+        if self.display_thread_id.contains(level) {
+            writer.write_str(" thread_id=")?;
+            write_logfmt_value(writer, &format!("{:?}", current_thread.id()))?;
+        }
+
+        writeln!(writer)
+    }
+
+    /// Writes the span names as `span=root>current`, followed by the fields of each span (from the
+    /// root span to the current span). Span fields are already formatted as logfmt by the field
+    /// formatter.
+    fn format_logfmt_spans<SubscriberT>(
+        &self,
+        ctx: &FmtContext<'_, SubscriberT, DevLogFieldFormat>,
+        writer: &mut Writer<'_>,
+    ) -> fmt::Result
+    where
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let Some(scope) = ctx.event_scope() else {
+            return Ok(());
+        };
+
+        let mut span_names = String::new();
+        let mut span_fields = String::new();
+        for span in scope
+            .from_root()
+            .filter(|span| self.displays_span(span.metadata()))
+        {
+            if !span_names.is_empty() {
+                span_names.push('>');
+            }
+            span_names.push_str(span.metadata().name());
+
+            let extensions = span.extensions();
+            if let Some(fields) = extensions.get::<SpanFields>() {
+                fields.format(
+                    ctx.field_format(),
+                    Writer::new(&mut span_fields),
+                    DevLogLayout::Expanded,
+                )?;
+            }
+        }
+
+        if span_names.is_empty() {
+            return Ok(());
+        }
+
+        writer.write_str(" span=")?;
+        write_logfmt_value(writer, &span_names)?;
+        writer.write_str(&span_fields)
+    }
+}
+
+/// Writes the given value, quoting and escaping it if it contains spaces, quotes, `=` or control
+/// characters (or if it is empty).
+pub(crate) fn write_logfmt_value(writer: &mut impl fmt::Write, value: &str) -> fmt::Result {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|char| char == ' ' || char == '=' || char == '"' || char.is_control());
+    if needs_quotes {
+        write_quoted(writer, value)
+    } else {
+        writer.write_str(value)
+    }
+}
+
+/// Writes the given value in double quotes, escaping quotes, backslashes and control characters.
+pub(crate) fn write_quoted(writer: &mut impl fmt::Write, value: &str) -> fmt::Result {
+    writer.write_char('"')?;
+    for char in value.chars() {
+        match char {
+            '"' => writer.write_str("\\\"")?,
+            '\\' => writer.write_str("\\\\")?,
+            '\n' => writer.write_str("\\n")?,
+            '\r' => writer.write_str("\\r")?,
+            '\t' => writer.write_str("\\t")?,
+            char if char.is_control() => write!(writer, "\\u{:04x}", char as u32)?,
+            char => writer.write_char(char)?,
+        }
+    }
+    writer.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_logfmt_value;
+
+    fn logfmt_value(value: &str) -> String {
+        let mut output = String::new();
+        write_logfmt_value(&mut output, value).unwrap();
+        output
+    }
+
+    #[test]
+    fn logfmt_values_are_only_quoted_when_needed() {
+        assert_eq!(logfmt_value("bob"), "bob");
+        assert_eq!(logfmt_value("größe"), "größe");
+        assert_eq!(logfmt_value(""), r#""""#);
+        assert_eq!(logfmt_value("a b"), r#""a b""#);
+        assert_eq!(logfmt_value("a=b"), r#""a=b""#);
+    }
+
+    #[test]
+    fn quoted_logfmt_values_escape_quotes_backslashes_and_control_characters() {
+        assert_eq!(
+            logfmt_value("say \"hi\"\\\n\t\x1b"),
+            r#""say \"hi\"\\\n\t\u001b""#
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use std::io;
+
+use tracing::{span, subscriber::Interest, Event, Level, Metadata, Subscriber};
+use tracing_core::Dispatch;
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    fmt::time::FormatTime,
+    layer::Context,
+    registry::LookupSpan,
+    Layer,
+};
+
+use crate::{
+    event_format::{is_log_bridge_callsite, normalized_metadata},
+    reload_handle::FilteredRegistry,
+    span_fields::SpanFieldsLayer,
+    DevLogSubscriberBuilder,
+};
+
+pub(crate) type BoxedLayer = Box<dyn Layer<FilteredRegistry> + Send + Sync>;
+
+/// An output added with [`DevLogSubscriberBuilder::with_output`]. Erases the timer type of the
+/// output's builder, so that outputs with different timers can be combined.
+pub(crate) trait Output: Send + Sync {
+    /// Builds the fmt layers of the output and its own additional outputs.
+    fn build_output_layers(&self) -> Vec<BoxedLayer>;
+
+    /// The most verbose level that the output (or its own additional outputs) displays or records.
+    fn max_level(&self) -> LevelFilter;
+
+    /// Whether the output (or one of its own additional outputs) displays events with the given
+    /// target and level.
+    fn would_display(&self, target: &str, level: Level) -> bool;
+
+    fn open_log_files(&self) -> io::Result<()>;
+}
+
+impl<TimeFormatT> Output for DevLogSubscriberBuilder<TimeFormatT>
+where
+    TimeFormatT: FormatTime + Clone + Send + Sync + 'static,
+{
+    fn build_output_layers(&self) -> Vec<BoxedLayer> {
+        DevLogSubscriberBuilder::build_output_layers(self)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        DevLogSubscriberBuilder::max_level(self)
+    }
+
+    fn would_display(&self, target: &str, level: Level) -> bool {
+        DevLogSubscriberBuilder::would_display(self, target, level)
+    }
+
+    fn open_log_files(&self) -> io::Result<()> {
+        DevLogSubscriberBuilder::open_log_files(self)
+    }
+}
+
+/// The layers of all outputs, and the layer that records span fields for them. A `Vec` of layers
+/// only enables the spans and events that every layer enables, while we want those that any
+/// output enables.
+pub(crate) struct OutputLayers {
+    span_fields: SpanFieldsLayer,
+    outputs: Vec<BoxedLayer>,
+}
+
+impl OutputLayers {
+    pub fn new(outputs: Vec<BoxedLayer>) -> Self {
+        Self {
+            span_fields: SpanFieldsLayer,
+            outputs,
+        }
+    }
+}
+
+impl Layer<FilteredRegistry> for OutputLayers {
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        for output in &self.outputs {
+            output.on_register_dispatch(subscriber);
+        }
+    }
+
+    fn on_layer(&mut self, subscriber: &mut FilteredRegistry) {
+        for output in &mut self.outputs {
+            output.on_layer(subscriber);
+        }
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        let mut interest = Interest::never();
+        for output in &self.outputs {
+            let output_interest = output.register_callsite(metadata);
+            if output_interest.is_always() {
+                return output_interest;
+            }
+            if output_interest.is_sometimes() {
+                interest = output_interest;
+            }
+        }
+        interest
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, FilteredRegistry>) -> bool {
+        self.outputs
+            .iter()
+            .any(|output| output.enabled(metadata, ctx.clone()))
+    }
+
+    fn on_new_span(
+        &self,
+        attributes: &span::Attributes<'_>,
+        id: &span::Id,
+        ctx: Context<'_, FilteredRegistry>,
+    ) {
+        self.span_fields.on_new_span(attributes, id, ctx.clone());
+        for output in &self.outputs {
+            output.on_new_span(attributes, id, ctx.clone());
+        }
+    }
+
+    fn on_record(
+        &self,
+        id: &span::Id,
+        values: &span::Record<'_>,
+        ctx: Context<'_, FilteredRegistry>,
+    ) {
+        self.span_fields.on_record(id, values, ctx.clone());
+        for output in &self.outputs {
+            output.on_record(id, values, ctx.clone());
+        }
+    }
+
+    fn on_follows_from(
+        &self,
+        id: &span::Id,
+        follows: &span::Id,
+        ctx: Context<'_, FilteredRegistry>,
+    ) {
+        for output in &self.outputs {
+            output.on_follows_from(id, follows, ctx.clone());
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, FilteredRegistry>) {
+        for output in &self.outputs {
+            output.on_event(event, ctx.clone());
+        }
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, FilteredRegistry>) {
+        for output in &self.outputs {
+            output.on_enter(id, ctx.clone());
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, FilteredRegistry>) {
+        for output in &self.outputs {
+            output.on_exit(id, ctx.clone());
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, FilteredRegistry>) {
+        for output in &self.outputs {
+            output.on_close(id.clone(), ctx.clone());
+        }
+    }
+
+    fn on_id_change(&self, old: &span::Id, new: &span::Id, ctx: Context<'_, FilteredRegistry>) {
+        for output in &self.outputs {
+            output.on_id_change(old, new, ctx.clone());
+        }
+    }
+}
+
+/// Wraps the fmt layer of an output, so that it only sees the events and spans that the output's
+/// per-target levels enable. We can't use the per-layer filters of `tracing-subscriber`
+/// ([`Layer::with_filter`]) for this, since they stop working when the layer is reloaded
+/// (see [tokio-rs/tracing#1629](https://github.com/tokio-rs/tracing/issues/1629)).
+pub(crate) struct FilteredOutput<LayerT> {
+    layer: LayerT,
+    filter: Targets,
+    /// Events that this filter enables, but that the layer should not receive.
+    excluded_events: Option<Targets>,
+}
+
+impl<LayerT> FilteredOutput<LayerT> {
+    pub fn new(layer: LayerT, filter: Targets) -> Self {
+        Self {
+            layer,
+            filter,
+            excluded_events: None,
+        }
+    }
+
+    /// Keeps events that the given filter enables from reaching the layer. Spans are not affected.
+    pub fn excluding(mut self, excluded_events: Targets) -> Self {
+        self.excluded_events = Some(excluded_events);
+        self
+    }
+
+    fn enables(&self, metadata: &Metadata<'_>) -> bool {
+        self.filter
+            .would_enable(metadata.target(), metadata.level())
+    }
+
+    fn enables_span<SubscriberT>(&self, id: &span::Id, ctx: &Context<'_, SubscriberT>) -> bool
+    where
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        ctx.metadata(id)
+            .is_some_and(|metadata| self.enables(metadata))
+    }
+}
+
+impl<SubscriberT, LayerT> Layer<SubscriberT> for FilteredOutput<LayerT>
+where
+    SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    LayerT: Layer<SubscriberT>,
+{
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        self.layer.on_register_dispatch(subscriber);
+    }
+
+    fn on_layer(&mut self, subscriber: &mut SubscriberT) {
+        self.layer.on_layer(subscriber);
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        if is_log_bridge_callsite(metadata) {
+            // The `log` bridge has one callsite per level for all targets, so we check the target
+            // of the original record for each event
+            Interest::sometimes()
+        } else if self.enables(metadata) {
+            self.layer.register_callsite(metadata)
+        } else {
+            Interest::never()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, SubscriberT>) -> bool {
+        // For records from the `log` crate, the bridge passes the metadata of the original record
+        self.enables(metadata) && self.layer.enabled(metadata, ctx)
+    }
+
+    fn on_new_span(
+        &self,
+        attributes: &span::Attributes<'_>,
+        id: &span::Id,
+        ctx: Context<'_, SubscriberT>,
+    ) {
+        if self.enables(attributes.metadata()) {
+            self.layer.on_new_span(attributes, id, ctx);
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, SubscriberT>) {
+        if self.enables_span(id, &ctx) {
+            self.layer.on_record(id, values, ctx);
+        }
+    }
+
+    fn on_follows_from(&self, id: &span::Id, follows: &span::Id, ctx: Context<'_, SubscriberT>) {
+        if self.enables_span(id, &ctx) {
+            self.layer.on_follows_from(id, follows, ctx);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, SubscriberT>) {
+        // Events reach every output if any output enables them. Events from the `log` bridge have
+        // the bridge's own target until normalized.
+        let normalized_metadata = normalized_metadata(event);
+        let metadata = normalized_metadata.as_ref().unwrap_or(event.metadata());
+        let excluded = self
+            .excluded_events
+            .as_ref()
+            .is_some_and(|excluded_events| {
+                excluded_events.would_enable(metadata.target(), metadata.level())
+            });
+        if self.enables(metadata) && !excluded {
+            self.layer.on_event(event, ctx);
+        }
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, SubscriberT>) {
+        if self.enables_span(id, &ctx) {
+            self.layer.on_enter(id, ctx);
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, SubscriberT>) {
+        if self.enables_span(id, &ctx) {
+            self.layer.on_exit(id, ctx);
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, SubscriberT>) {
+        if self.enables_span(&id, &ctx) {
+            self.layer.on_close(id, ctx);
+        }
+    }
+
+    fn on_id_change(&self, old: &span::Id, new: &span::Id, ctx: Context<'_, SubscriberT>) {
+        if self.enables_span(old, &ctx) {
+            self.layer.on_id_change(old, new, ctx);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    panic, thread,
+};
+
+use crate::event_format::is_formatting_event;
+
+/// Installs a panic hook that logs panics as devlog error events, so that they don't break the
+/// visual flow of the log output. Since the hook runs on the panicking thread, the event includes
+/// the spans that were active when the panic occurred.
+///
+/// The previously installed panic hook (usually Rust's default one, which prints to stderr) is
+/// called instead when `is_displayed` returns false, i.e. when the subscriber would not display
+/// an error event with target `panic`, so that panics are never silently dropped. It is also
+/// called when the panic comes from a field's `Display` or `Debug` implementation while a log
+/// event is being formatted, since logging the panic would re-enter the subscriber in the middle
+/// of that event.
+pub(crate) fn install_panic_hook(is_displayed: impl Fn() -> bool + Send + Sync + 'static) {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        if is_formatting_event() || !is_displayed() {
+            previous_hook(panic_info);
+            return;
+        }
+
+        let payload = panic_info.payload();
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.as_str()
+        } else {
+            "Box<dyn Any>"
+        };
+
+        let location = panic_info
+            .location()
+            .map(|location| location.to_string())
+            .unwrap_or_else(|| String::from("<unknown>"));
+
+        let current_thread = thread::current();
+        let thread_name = current_thread.name().unwrap_or("<unnamed>");
+
+        // Like the default panic hook, this only captures a backtrace if enabled by the
+        // RUST_BACKTRACE or RUST_LIB_BACKTRACE environment variables
+        let backtrace = Backtrace::capture();
+
+        match backtrace.status() {
+            BacktraceStatus::Captured => tracing::error!(
+                target: "panic",
+                location,
+                thread = thread_name,
+                // Starts the backtrace on its own line, so that the frames are aligned
+                backtrace = %format_args!("\n{}", backtrace.to_string().trim_end()),
+                "Panicked: {message}",
+            ),
+            _ => tracing::error!(
+                target: "panic",
+                location,
+                thread = thread_name,
+                "Panicked: {message}",
+            ),
+        }
+    }));
+}
@@ -0,0 +1,291 @@
+use core::fmt;
+use std::{
+    collections::HashMap,
+    io::Write as _,
+    sync::{Arc, Mutex, PoisonError, Weak},
+    thread,
+    time::{Duration, Instant},
+};
+
+use tracing::{Event, Metadata};
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::{
+    color::{COLOR_GRAY, COLOR_RESET},
+    event_format::{normalized_metadata, CallsiteKey},
+    writer::DevLogWriter,
+};
+
+/// How long a callsite must be quiet after dropping events before the number of dropped events is
+/// reported, so that the drops at the end of a burst are not left unreported until the callsite
+/// logs again.
+const QUIET_PERIOD: Duration = Duration::from_secs(1);
+
+/// Limits how many log events each callsite can emit per time period, so that hot loops don't
+/// flood the terminal. Events over the limit are dropped, and the number of dropped events is
+/// reported the next time the callsite is allowed to log, when the callsite has not logged for a
+/// second, or when the subscriber is dropped (or the [`SummaryGuard`](crate::SummaryGuard) is).
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    max_events: usize,
+    period: Duration,
+    state: Mutex<LimiterState>,
+    /// Where the numbers of dropped events are written when they are not reported along with a
+    /// log event.
+    writer: Mutex<Option<DevLogWriter>>,
+    /// Passed to the thread that reports dropped events after the quiet period, so that the
+    /// thread does not keep the limiter alive.
+    this: Weak<RateLimiter>,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    callsites: HashMap<CallsiteKey, CallsiteState>,
+    /// Whether the thread that reports dropped events after the quiet period is running.
+    timer_running: bool,
+}
+
+#[derive(Debug)]
+struct CallsiteState {
+    period_start: Instant,
+    events_in_period: usize,
+    dropped: Option<DroppedEvents>,
+}
+
+/// The events that a callsite dropped since it last logged.
+#[derive(Debug)]
+struct DroppedEvents {
+    count: usize,
+    first_dropped: Instant,
+    last_dropped: Instant,
+    target: String,
+    file: Option<String>,
+    line: Option<u32>,
+    /// Whether the callsite's events are written with colors, so that the report can match them.
+    ansi: bool,
+}
+
+/// What to do with a log event, as decided by [`RateLimiter::check`].
+pub(crate) struct RateLimitResult {
+    /// Whether the event is over the rate limit, and should be dropped.
+    pub drop: bool,
+    /// Events from the same callsite that were dropped since it last logged, which should be
+    /// reported before this event is printed.
+    pub report: Option<DropReport>,
+}
+
+/// Displays as `(rate limit: dropped 4213 events from app::worker at src/worker.rs:42 over 1.0s)`.
+pub(crate) struct DropReport {
+    count: usize,
+    duration: Duration,
+    target: String,
+    file: Option<String>,
+    line: Option<u32>,
+}
+
+impl RateLimiter {
+    /// `max_events` must be at least 1.
+    pub fn new(max_events: usize, period: Duration) -> Arc<Self> {
+        Arc::new_cyclic(|this| Self {
+            max_events,
+            period,
+            state: Mutex::new(LimiterState::default()),
+            writer: Mutex::new(None),
+            this: this.clone(),
+        })
+    }
+
+    /// Sets where the numbers of dropped events are written when they are reported without a log
+    /// event.
+    pub fn set_writer(&self, writer: DevLogWriter) {
+        *self.writer.lock().unwrap_or_else(PoisonError::into_inner) = Some(writer);
+    }
+
+    /// `ansi` is whether the event is written with colors.
+    pub fn check(&self, event: &Event<'_>, ansi: bool) -> RateLimitResult {
+        // Events from the `log` crate are limited per original callsite, and reported with its
+        // target and source location
+        let normalized_metadata = normalized_metadata(event);
+        let key = CallsiteKey::new(event, normalized_metadata.as_ref());
+        let metadata = normalized_metadata.as_ref().unwrap_or(event.metadata());
+        let now = Instant::now();
+
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let state = &mut *state;
+
+        let callsite = state.callsites.entry(key).or_insert_with(|| CallsiteState {
+            period_start: now,
+            events_in_period: 0,
+            dropped: None,
+        });
+
+        if now.duration_since(callsite.period_start) >= self.period {
+            callsite.period_start = now;
+            callsite.events_in_period = 0;
+        }
+
+        if callsite.events_in_period >= self.max_events {
+            let dropped = callsite
+                .dropped
+                .get_or_insert_with(|| DroppedEvents::new(metadata, now, ansi));
+            dropped.count += 1;
+            dropped.last_dropped = now;
+
+            if !state.timer_running {
+                state.timer_running = self.spawn_timer();
+            }
+
+            return RateLimitResult {
+                drop: true,
+                report: None,
+            };
+        }
+
+        callsite.events_in_period += 1;
+        let report = callsite.dropped.take().map(|dropped| dropped.report(now));
+        RateLimitResult {
+            drop: false,
+            report,
+        }
+    }
+
+    /// Reports the events that were dropped and have not been reported yet, if any.
+    pub fn flush(&self) {
+        let now = Instant::now();
+        let pending: Vec<DroppedEvents> = self
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .callsites
+            .values_mut()
+            .filter_map(|callsite| callsite.dropped.take())
+            .collect();
+
+        for dropped in pending {
+            let ansi = dropped.ansi;
+            self.write_report(&dropped.report(now), ansi);
+        }
+    }
+
+    /// Starts the thread that reports dropped events after the quiet period. Returns whether it
+    /// started.
+    fn spawn_timer(&self) -> bool {
+        let this = self.this.clone();
+        thread::Builder::new()
+            .name("devlog-rate-limit".to_string())
+            .spawn(move || run_timer(this))
+            .is_ok()
+    }
+
+    /// Reports the dropped events of the callsites that have been quiet for [`QUIET_PERIOD`].
+    /// Returns whether there are still unreported dropped events to wait for, and stops the timer
+    /// otherwise.
+    fn flush_if_quiet(&self) -> bool {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let mut quiet = Vec::new();
+        let mut waiting = false;
+        for callsite in state.callsites.values_mut() {
+            match &callsite.dropped {
+                Some(dropped) if now.duration_since(dropped.last_dropped) >= QUIET_PERIOD => {
+                    quiet.extend(callsite.dropped.take());
+                }
+                Some(_) => waiting = true,
+                None => {}
+            }
+        }
+        state.timer_running = waiting;
+        drop(state);
+
+        for dropped in quiet {
+            let ansi = dropped.ansi;
+            self.write_report(&dropped.report(now), ansi);
+        }
+        waiting
+    }
+
+    /// Writes the number of dropped events on its own line, like `format_devlog_event` does when
+    /// reporting it along with a log event.
+    fn write_report(&self, report: &DropReport, ansi: bool) {
+        let Some(writer) = self
+            .writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+        else {
+            return;
+        };
+
+        let line = if ansi {
+            format!("{COLOR_GRAY}{report}{COLOR_RESET}\n")
+        } else {
+            format!("{report}\n")
+        };
+        // There is nowhere to report a failure to write the number of dropped events, so we
+        // ignore it
+        let _ = writer.make_writer().write_all(line.as_bytes());
+    }
+}
+
+impl Drop for RateLimiter {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+fn run_timer(rate_limiter: Weak<RateLimiter>) {
+    loop {
+        thread::sleep(QUIET_PERIOD);
+        let Some(rate_limiter) = rate_limiter.upgrade() else {
+            return;
+        };
+        if !rate_limiter.flush_if_quiet() {
+            return;
+        }
+    }
+}
+
+impl DroppedEvents {
+    fn new(metadata: &Metadata<'_>, now: Instant, ansi: bool) -> Self {
+        Self {
+            count: 0,
+            first_dropped: now,
+            last_dropped: now,
+            target: metadata.target().to_string(),
+            file: metadata.file().map(str::to_string),
+            line: metadata.line(),
+            ansi,
+        }
+    }
+
+    fn report(self, now: Instant) -> DropReport {
+        DropReport {
+            count: self.count,
+            duration: now.duration_since(self.first_dropped),
+            target: self.target,
+            file: self.file,
+            line: self.line,
+        }
+    }
+}
+
+impl fmt::Display for DropReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let events = if self.count == 1 { "event" } else { "events" };
+        write!(
+            f,
+            "(rate limit: dropped {} {events} from {}",
+            self.count, self.target
+        )?;
+
+        if let Some(file) = &self.file {
+            write!(f, " at {file}")?;
+            if let Some(line) = self.line {
+                write!(f, ":{line}")?;
+            }
+        }
+
+        write!(f, " over {:.1}s)", self.duration.as_secs_f64())
+    }
+}
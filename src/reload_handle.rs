@@ -0,0 +1,145 @@
+use std::{
+    cell::Cell,
+    mem,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use tracing::Level;
+use tracing_subscriber::{
+    filter::LevelFilter, fmt::time::FormatTime, layer::Layered, registry::Registry, reload,
+};
+
+use crate::{output::OutputLayers, DevLogSubscriberBuilder};
+
+pub(crate) type FilterLayer = reload::Layer<LevelFilter, Registry>;
+pub(crate) type FilteredRegistry = Layered<FilterLayer, Registry>;
+
+/// Changes the settings of the global devlog subscriber while the program is running, e.g. to show
+/// source locations or raise the verbosity of a running dev server without restarting it.
+/// Returned by [`DevLogSubscriberBuilder::init`].
+///
+/// ### Example
+///
+/// ```
+/// use tracing::level_filters::LevelFilter;
+///
+/// let reload_handle = devlog_tracing::subscriber().init();
+///
+/// // Later, e.g. in response to a debug endpoint being called:
+/// reload_handle
+///     .modify(|builder| {
+///         builder
+///             .with_max_level(LevelFilter::DEBUG)
+///             .with_source_location(true)
+///     })
+///     .expect("Failed to reload log subscriber");
+/// ```
+#[derive(Clone)]
+pub struct DevLogReloadHandle<TimeFormatT> {
+    /// The settings currently in use, which [`DevLogReloadHandle::modify`] applies changes on top
+    /// of.
+    settings: Arc<Mutex<DevLogSubscriberBuilder<TimeFormatT>>>,
+    filter_handle: reload::Handle<LevelFilter, Registry>,
+    /// The layers of the subscriber's outputs.
+    output_handle: reload::Handle<OutputLayers, FilteredRegistry>,
+    #[cfg(feature = "log")]
+    log_bridge_installed: bool,
+}
+
+impl<TimeFormatT> DevLogReloadHandle<TimeFormatT>
+where
+    TimeFormatT: FormatTime + Clone + Send + Sync + 'static,
+{
+    pub(crate) fn new(
+        settings: DevLogSubscriberBuilder<TimeFormatT>,
+        filter_handle: reload::Handle<LevelFilter, Registry>,
+        output_handle: reload::Handle<OutputLayers, FilteredRegistry>,
+        #[cfg(feature = "log")] log_bridge_installed: bool,
+    ) -> Self {
+        Self {
+            settings: Arc::new(Mutex::new(settings)),
+            filter_handle,
+            output_handle,
+            #[cfg(feature = "log")]
+            log_bridge_installed,
+        }
+    }
+
+    /// Whether the subscriber currently displays events with the given target and level. If another
+    /// thread is modifying the settings, this waits for it to finish. Returns `None` if the current
+    /// thread is modifying the settings (e.g. when called from a panic in
+    /// [`DevLogReloadHandle::modify`]), since waiting would deadlock.
+    pub(crate) fn would_display(&self, target: &str, level: Level) -> Option<bool> {
+        if MODIFYING_SETTINGS.get() {
+            return None;
+        }
+        let settings = self.settings.lock().unwrap_or_else(PoisonError::into_inner);
+        Some(settings.would_display(target, level))
+    }
+
+    /// Calls the given function with a builder holding the current settings of the subscriber, and
+    /// applies the settings of the returned builder. Filters (such as
+    /// [`with_max_level`](DevLogSubscriberBuilder::with_max_level) and
+    /// [`with_target_level`](DevLogSubscriberBuilder::with_target_level)) and display options (such
+    /// as [`with_source_location`](DevLogSubscriberBuilder::with_source_location) and
+    /// [`with_spans`](DevLogSubscriberBuilder::with_spans)) take effect for the next log event.
+    ///
+    /// Additional outputs (see [`with_output`](DevLogSubscriberBuilder::with_output)) can be added
+    /// or replaced here too, but settings that only apply when the subscriber is initialized (the
+    /// panic hook and the `log` bridge) can't be changed.
+    pub fn modify(
+        &self,
+        modify: impl FnOnce(
+            DevLogSubscriberBuilder<TimeFormatT>,
+        ) -> DevLogSubscriberBuilder<TimeFormatT>,
+    ) -> Result<(), reload::Error> {
+        let mut settings = self.settings.lock().unwrap_or_else(PoisonError::into_inner);
+        let _modifying_settings = ModifyingSettingsGuard::enter();
+
+        let mut new_settings = modify(settings.clone());
+        let config_warnings = mem::take(&mut new_settings.config_warnings);
+
+        self.output_handle.reload(new_settings.build_layers())?;
+        self.filter_handle.reload(new_settings.max_level())?;
+        *settings = new_settings;
+        drop(_modifying_settings);
+        drop(settings);
+
+        // Reloading the filter updates the max level of `tracing`, but the `log` crate keeps its
+        // own max level, which the log bridge set when it was installed
+        #[cfg(feature = "log")]
+        if self.log_bridge_installed {
+            use tracing_log::AsLog;
+            tracing_log::log::set_max_level(LevelFilter::current().as_log());
+        }
+
+        for warning in config_warnings {
+            tracing::warn!(target: "devlog_tracing", "{warning}");
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    static MODIFYING_SETTINGS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Marks the current thread as holding the settings lock in [`DevLogReloadHandle::modify`] until
+/// dropped (including when unwinding).
+struct ModifyingSettingsGuard {
+    was_modifying: bool,
+}
+
+impl ModifyingSettingsGuard {
+    fn enter() -> Self {
+        Self {
+            was_modifying: MODIFYING_SETTINGS.replace(true),
+        }
+    }
+}
+
+impl Drop for ModifyingSettingsGuard {
+    fn drop(&mut self) {
+        MODIFYING_SETTINGS.set(self.was_modifying);
+    }
+}
@@ -0,0 +1,303 @@
+use core::fmt;
+use std::{
+    fmt::Write as _,
+    io::Write as _,
+    sync::{Arc, Mutex, PoisonError, Weak},
+    thread,
+    time::{Duration, Instant},
+};
+
+use tracing::{
+    field::{Field, Visit},
+    Event,
+};
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::{
+    color::{COLOR_GRAY, COLOR_RESET},
+    event_format::{normalized_metadata, CallsiteKey},
+    writer::DevLogWriter,
+};
+
+/// How long a run of repeated events must be quiet before its repeat count is reported, so that
+/// the last repeats of a run are not left unreported until the next log event.
+const QUIET_PERIOD: Duration = Duration::from_secs(1);
+
+/// Detects consecutive log events from the same callsite with the same field values, so that we
+/// can fold them into a single "repeated N times" line instead of printing every one of them.
+///
+/// The repeats of the last event are reported when a different event is logged, when the repeats
+/// have been quiet for a second, and when the subscriber is dropped (or the
+/// [`SummaryGuard`](crate::SummaryGuard) is). Repeats in the last second before the process exits
+/// may still go unreported if none of these happen.
+#[derive(Debug)]
+pub(crate) struct RepetitionFolder {
+    /// How often to report the repeat count while a run of repeated events is still going on.
+    flush_interval: Option<Duration>,
+    state: Mutex<FolderState>,
+    /// Where the repeat counts are written when they are not reported along with a log event.
+    writer: Mutex<Option<DevLogWriter>>,
+    /// Passed to the thread that reports repeats after the quiet period, so that the thread does
+    /// not keep the folder alive.
+    this: Weak<RepetitionFolder>,
+}
+
+#[derive(Debug, Default)]
+struct FolderState {
+    last_event: Option<LastEvent>,
+    /// Whether the thread that reports repeats after the quiet period is running.
+    timer_running: bool,
+}
+
+#[derive(Debug)]
+struct LastEvent {
+    key: EventKey,
+    /// The number of repeats of the event since it was last printed or reported.
+    repeat_count: usize,
+    /// When the repeats that we have not yet reported started, i.e. when the event was printed or
+    /// last reported.
+    since: Instant,
+    last_repeat: Instant,
+    /// Whether the event was written with colors, so that its repeat count can match it.
+    ansi: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct EventKey {
+    callsite: CallsiteKey,
+    /// The field values of the event, formatted with their `Debug` implementation.
+    fields: String,
+}
+
+/// What to do with a log event, as decided by [`RepetitionFolder::check`].
+pub(crate) struct FoldResult {
+    /// Whether the event is a repeat of the previous event, and should be omitted.
+    pub fold: bool,
+    /// Repeats of the previous event that should be reported before this event is printed (or in
+    /// place of it, if it is folded).
+    pub report: Option<RepeatReport>,
+}
+
+/// Displays as `(repeated 42 times over 3.1s)`.
+pub(crate) struct RepeatReport {
+    count: usize,
+    duration: Duration,
+}
+
+impl RepetitionFolder {
+    pub fn new(flush_interval: Option<Duration>) -> Arc<Self> {
+        Arc::new_cyclic(|this| Self {
+            flush_interval,
+            state: Mutex::new(FolderState::default()),
+            writer: Mutex::new(None),
+            this: this.clone(),
+        })
+    }
+
+    /// Sets where repeat counts are written when they are reported without a log event.
+    pub fn set_writer(&self, writer: DevLogWriter) {
+        *self.writer.lock().unwrap_or_else(PoisonError::into_inner) = Some(writer);
+    }
+
+    /// `ansi` is whether the event is written with colors.
+    pub fn check(&self, event: &Event<'_>, ansi: bool) -> FoldResult {
+        let key = EventKey::new(event);
+        let now = Instant::now();
+
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let state = &mut *state;
+
+        match state.last_event.as_mut() {
+            Some(last_event) if last_event.key == key => {
+                last_event.repeat_count += 1;
+                last_event.last_repeat = now;
+
+                let should_flush = self
+                    .flush_interval
+                    .is_some_and(|interval| now.duration_since(last_event.since) >= interval);
+                let report = if should_flush {
+                    last_event.take_report(now)
+                } else {
+                    None
+                };
+
+                if !state.timer_running {
+                    state.timer_running = self.spawn_timer();
+                }
+
+                FoldResult { fold: true, report }
+            }
+            _ => {
+                let report = state
+                    .last_event
+                    .take()
+                    .and_then(|mut last_event| last_event.take_report(now));
+
+                state.last_event = Some(LastEvent {
+                    key,
+                    repeat_count: 0,
+                    since: now,
+                    last_repeat: now,
+                    ansi,
+                });
+
+                FoldResult {
+                    fold: false,
+                    report,
+                }
+            }
+        }
+    }
+
+    /// Reports the repeats of the last event that have not been reported yet, if any.
+    pub fn flush(&self) {
+        let pending = self
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .last_event
+            .take()
+            .and_then(|mut last_event| {
+                let report = last_event.take_report(Instant::now())?;
+                Some((report, last_event.ansi))
+            });
+
+        if let Some((report, ansi)) = pending {
+            self.write_report(&report, ansi);
+        }
+    }
+
+    /// Starts the thread that reports repeats after the quiet period. Returns whether it started.
+    fn spawn_timer(&self) -> bool {
+        let this = self.this.clone();
+        thread::Builder::new()
+            .name("devlog-repetitions".to_string())
+            .spawn(move || run_timer(this))
+            .is_ok()
+    }
+
+    /// Reports the repeats of the last event if they have been quiet for [`QUIET_PERIOD`]. Returns
+    /// whether there are still unreported repeats to wait for, and stops the timer otherwise.
+    fn flush_if_quiet(&self) -> bool {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let Some(last_event) = &mut state.last_event else {
+            state.timer_running = false;
+            return false;
+        };
+        if last_event.repeat_count == 0 {
+            state.timer_running = false;
+            return false;
+        }
+        if now.duration_since(last_event.last_repeat) < QUIET_PERIOD {
+            return true;
+        }
+
+        // The run of repeats is over, so the next occurrence of the event is printed again
+        let ansi = last_event.ansi;
+        let report = last_event.take_report(now);
+        state.last_event = None;
+        state.timer_running = false;
+        drop(state);
+
+        if let Some(report) = report {
+            self.write_report(&report, ansi);
+        }
+        false
+    }
+
+    /// Writes the repeat count on its own line, like `format_repeat_report` does when reporting it
+    /// along with a log event.
+    fn write_report(&self, report: &RepeatReport, ansi: bool) {
+        let Some(writer) = self
+            .writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+        else {
+            return;
+        };
+
+        let line = if ansi {
+            format!("  {COLOR_GRAY}{report}{COLOR_RESET}\n")
+        } else {
+            format!("  {report}\n")
+        };
+        // There is nowhere to report a failure to write the repeat count, so we ignore it
+        let _ = writer.make_writer().write_all(line.as_bytes());
+    }
+}
+
+impl Drop for RepetitionFolder {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+fn run_timer(folder: Weak<RepetitionFolder>) {
+    loop {
+        thread::sleep(QUIET_PERIOD);
+        let Some(folder) = folder.upgrade() else {
+            return;
+        };
+        if !folder.flush_if_quiet() {
+            return;
+        }
+    }
+}
+
+impl LastEvent {
+    /// Returns a report of the repeats since the event was last printed or reported (if any), and
+    /// resets the repeat count.
+    fn take_report(&mut self, now: Instant) -> Option<RepeatReport> {
+        if self.repeat_count == 0 {
+            return None;
+        }
+
+        let report = RepeatReport {
+            count: self.repeat_count,
+            duration: self.last_repeat.duration_since(self.since),
+        };
+        self.repeat_count = 0;
+        self.since = now;
+        Some(report)
+    }
+}
+
+impl EventKey {
+    fn new(event: &Event<'_>) -> Self {
+        let mut visitor = FieldCollector {
+            fields: String::new(),
+        };
+        event.record(&mut visitor);
+
+        Self {
+            callsite: CallsiteKey::new(event, normalized_metadata(event).as_ref()),
+            fields: visitor.fields,
+        }
+    }
+}
+
+struct FieldCollector {
+    fields: String,
+}
+
+impl Visit for FieldCollector {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        // Writing to a String can't fail
+        let _ = write!(self.fields, "{}={:?};", field.name(), value);
+    }
+}
+
+impl fmt::Display for RepeatReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let times = if self.count == 1 { "time" } else { "times" };
+        write!(
+            f,
+            "(repeated {} {times} over {:.1}s)",
+            self.count,
+            self.duration.as_secs_f64()
+        )
+    }
+}
@@ -0,0 +1,323 @@
+use std::{
+    borrow::Cow,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// Creates a link to a source file location from the given URL template. See
+/// [`DevLogSubscriberBuilder::with_source_links`](crate::DevLogSubscriberBuilder::with_source_links)
+/// for the supported placeholders. Paths are percent-encoded, so that paths with spaces or
+/// characters like `#` don't break the link.
+pub(crate) fn source_link(
+    template: &str,
+    file_name: &str,
+    line_number: Option<u32>,
+    workspace_root: Option<&Path>,
+) -> String {
+    let mut link = template
+        .replace("{path}", &percent_encode_path(file_name))
+        .replace("{line}", &line_number.unwrap_or(1).to_string());
+
+    if link.contains("{abs_path}") {
+        let absolute_path = absolute_path(file_name, workspace_root);
+        link = link.replace("{abs_path}", &percent_encode_path(&absolute_path));
+    }
+
+    link
+}
+
+/// `tracing` gives us the path from `file!()`, which is relative to the workspace root for crates
+/// in the current workspace, so we resolve relative paths against that.
+fn absolute_path<'a>(file_name: &'a str, workspace_root: Option<&Path>) -> Cow<'a, str> {
+    let path = Path::new(file_name);
+    if path.is_absolute() {
+        return Cow::Borrowed(file_name);
+    }
+
+    match workspace_root.or_else(detected_workspace_root) {
+        Some(workspace_root) => {
+            Cow::Owned(workspace_root.join(path).to_string_lossy().into_owned())
+        }
+        None => Cow::Borrowed(file_name),
+    }
+}
+
+/// Finds the root of the Cargo workspace that the program was built from, by walking up from the
+/// `CARGO_MANIFEST_DIR` environment variable (set by `cargo run` and `cargo test`) to the first
+/// `Cargo.toml` with a `[workspace]` table. If there is none, the package is its own workspace.
+/// Falls back to the current directory when the program is not run through Cargo.
+fn detected_workspace_root<'a>() -> Option<&'a Path> {
+    static WORKSPACE_ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+    WORKSPACE_ROOT
+        .get_or_init(|| {
+            let Some(manifest_dir) = env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from) else {
+                return env::current_dir().ok();
+            };
+
+            let workspace_root = manifest_dir.ancestors().find(|dir| {
+                fs::read_to_string(dir.join("Cargo.toml")).is_ok_and(|manifest| {
+                    manifest
+                        .lines()
+                        .any(|line| line.trim_start().starts_with("[workspace"))
+                })
+            });
+            Some(workspace_root.unwrap_or(&manifest_dir).to_path_buf())
+        })
+        .as_deref()
+}
+
+/// Percent-encodes every character in the path that is not allowed in a URL path, except for `/`
+/// and `:` (for Windows drive letters). Backslashes are converted to `/`.
+fn percent_encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for char in path.chars() {
+        match char {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '.' | '_' | '~' | '/' | ':' => {
+                encoded.push(char)
+            }
+            '\\' => encoded.push('/'),
+            char => {
+                let mut bytes = [0; 4];
+                for byte in char.encode_utf8(&mut bytes).bytes() {
+                    encoded.push_str(&format!("%{byte:02X}"));
+                }
+            }
+        }
+    }
+    encoded
+}
+
+/// How source file paths are displayed in log events.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SourcePathStyle {
+    /// Displays paths as reported by `tracing`. This is the default.
+    #[default]
+    Full,
+    /// Strips the workspace root (see
+    /// [`DevLogSubscriberBuilder::with_workspace_root`](crate::DevLogSubscriberBuilder::with_workspace_root))
+    /// from absolute paths, and collapses paths to
+    /// dependencies in the Cargo registry or Git checkouts to `<crate>@<version>/...`:
+    /// ```text
+    /// /home/me/.cargo/registry/src/index.crates.io-6f17d22bba15001f/hyper-1.4.1/src/proto/h1/conn.rs
+    /// ```
+    /// ...becomes:
+    /// ```text
+    /// hyper@1.4.1/src/proto/h1/conn.rs
+    /// ```
+    Short,
+    /// Same as [`SourcePathStyle::Short`], but also abbreviates intermediate directories to their
+    /// first character:
+    /// ```text
+    /// hyper@1.4.1/s/p/h/conn.rs
+    /// ```
+    Abbreviated,
+}
+
+const REGISTRY_SOURCE_DIR: &str = "registry/src/";
+const GIT_CHECKOUT_DIR: &str = "git/checkouts/";
+
+pub(crate) fn shorten_path<'a>(
+    file_name: &'a str,
+    style: SourcePathStyle,
+    workspace_root: Option<&Path>,
+) -> Cow<'a, str> {
+    if style == SourcePathStyle::Full {
+        return Cow::Borrowed(file_name);
+    }
+
+    let path = strip_dependency_dir(file_name)
+        .or_else(|| strip_workspace_root(file_name, workspace_root).map(Cow::Borrowed))
+        .unwrap_or(Cow::Borrowed(file_name));
+
+    match style {
+        SourcePathStyle::Abbreviated => Cow::Owned(abbreviate_dirs(&path)),
+        SourcePathStyle::Full | SourcePathStyle::Short => path,
+    }
+}
+
+/// Collapses paths on the form `<cargo home>/registry/src/<index>/<crate>-<version>/...` to
+/// `<crate>@<version>/...`, and `<cargo home>/git/checkouts/<repo>-<hash>/<revision>/...` to
+/// `<repo>@<revision>/...`.
+fn strip_dependency_dir(file_name: &str) -> Option<Cow<'_, str>> {
+    let normalized = file_name.replace('\\', "/");
+
+    if let Some(index) = normalized.find(REGISTRY_SOURCE_DIR) {
+        let (_registry_index, rest) =
+            normalized[index + REGISTRY_SOURCE_DIR.len()..].split_once('/')?;
+        let (package_dir, rest) = rest.split_once('/')?;
+        let (crate_name, version) = split_crate_version(package_dir)?;
+        return Some(Cow::Owned(format!("{crate_name}@{version}/{rest}")));
+    }
+
+    if let Some(index) = normalized.find(GIT_CHECKOUT_DIR) {
+        let (repo_dir, rest) = normalized[index + GIT_CHECKOUT_DIR.len()..].split_once('/')?;
+        let (revision, rest) = rest.split_once('/')?;
+        // Checkout directories are named "<repo>-<hash>"
+        let repo_name = repo_dir
+            .rsplit_once('-')
+            .map_or(repo_dir, |(name, _hash)| name);
+        return Some(Cow::Owned(format!("{repo_name}@{revision}/{rest}")));
+    }
+
+    None
+}
+
+/// Splits a package directory name like `hyper-1.4.1` into crate name and version. Crate names may
+/// contain dashes followed by digits (e.g. `sha-1`), so we look for the first dash that is followed
+/// by something that looks like a version (`<major>.<minor>`).
+fn split_crate_version(package_dir: &str) -> Option<(&str, &str)> {
+    package_dir
+        .match_indices('-')
+        .map(|(index, _)| (&package_dir[..index], &package_dir[index + 1..]))
+        .find(|(_, version)| {
+            let mut parts = version.splitn(3, '.');
+            let is_number = |part: Option<&str>| {
+                part.is_some_and(|part| {
+                    !part.is_empty() && part.chars().all(|c| c.is_ascii_digit())
+                })
+            };
+            is_number(parts.next()) && is_number(parts.next())
+        })
+}
+
+fn strip_workspace_root<'a>(file_name: &'a str, workspace_root: Option<&Path>) -> Option<&'a str> {
+    let workspace_root = workspace_root.or_else(detected_workspace_root)?;
+    let relative_path = Path::new(file_name).strip_prefix(workspace_root).ok()?;
+    relative_path.to_str()
+}
+
+/// Abbreviates every directory in the path to its first character, except for the file name and
+/// `<crate>@<version>` directories.
+fn abbreviate_dirs(path: &str) -> String {
+    let mut components = path.split(['/', '\\']).peekable();
+    let mut abbreviated = String::with_capacity(path.len());
+
+    while let Some(component) = components.next() {
+        let is_file_name = components.peek().is_none();
+        if is_file_name || component.contains('@') {
+            abbreviated.push_str(component);
+        } else if let Some(first_char) = component.chars().next() {
+            abbreviated.push(first_char);
+        }
+
+        if !is_file_name {
+            abbreviated.push('/');
+        }
+    }
+
+    abbreviated
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{shorten_path, source_link, SourcePathStyle};
+
+    #[test]
+    fn source_links_percent_encode_paths() {
+        let link = source_link(
+            "idea://open?file={abs_path}&line={line}",
+            "src/my file#1.rs",
+            Some(12),
+            Some(Path::new("/home/me/my project")),
+        );
+
+        assert_eq!(
+            link,
+            "idea://open?file=/home/me/my%20project/src/my%20file%231.rs&line=12"
+        );
+    }
+
+    #[test]
+    fn source_links_encode_non_ascii_characters_as_utf8() {
+        let link = source_link("file://{path}", "src/größe.rs", None, None);
+
+        assert_eq!(link, "file://src/gr%C3%B6%C3%9Fe.rs");
+    }
+
+    #[test]
+    fn source_links_resolve_paths_against_the_cargo_workspace_by_default() {
+        let link = source_link("file://{abs_path}", "src/lib.rs", None, None);
+
+        assert_eq!(
+            link,
+            format!("file://{}/src/lib.rs", env!("CARGO_MANIFEST_DIR"))
+        );
+    }
+
+    const REGISTRY_PATH: &str = "/home/me/.cargo/registry/src/\
+        index.crates.io-6f17d22bba15001f/hyper-1.4.1/src/proto/h1/conn.rs";
+
+    #[test]
+    fn full_paths_are_kept_as_is() {
+        let path = shorten_path(REGISTRY_PATH, SourcePathStyle::Full, None);
+
+        assert_eq!(path, REGISTRY_PATH);
+    }
+
+    #[test]
+    fn short_paths_are_relative_to_the_workspace_root() {
+        let path = shorten_path(
+            "/home/me/project/src/server.rs",
+            SourcePathStyle::Short,
+            Some(Path::new("/home/me/project")),
+        );
+
+        assert_eq!(path, "src/server.rs");
+    }
+
+    #[test]
+    fn short_paths_collapse_registry_dependencies() {
+        let path = shorten_path(REGISTRY_PATH, SourcePathStyle::Short, None);
+
+        assert_eq!(path, "hyper@1.4.1/src/proto/h1/conn.rs");
+    }
+
+    #[test]
+    fn short_paths_keep_dashes_in_crate_names() {
+        let path = shorten_path(
+            "/home/me/.cargo/registry/src/index.crates.io-6f17d22bba15001f/sha-1-0.10.1/src/lib.rs",
+            SourcePathStyle::Short,
+            None,
+        );
+
+        assert_eq!(path, "sha-1@0.10.1/src/lib.rs");
+    }
+
+    #[test]
+    fn short_paths_collapse_git_dependencies() {
+        let path = shorten_path(
+            "/home/me/.cargo/git/checkouts/tokio-2f3a9c1b7d4e6f80/abc1234/tokio/src/lib.rs",
+            SourcePathStyle::Short,
+            None,
+        );
+
+        assert_eq!(path, "tokio@abc1234/tokio/src/lib.rs");
+    }
+
+    #[test]
+    fn abbreviated_paths_shorten_directories_to_their_first_character() {
+        let path = shorten_path(REGISTRY_PATH, SourcePathStyle::Abbreviated, None);
+
+        assert_eq!(path, "hyper@1.4.1/s/p/h/conn.rs");
+    }
+
+    #[test]
+    fn paths_outside_the_workspace_are_kept() {
+        let path = shorten_path(
+            "/usr/src/other/lib.rs",
+            SourcePathStyle::Short,
+            Some(Path::new("/home/me/project")),
+        );
+
+        assert_eq!(path, "/usr/src/other/lib.rs");
+    }
+}
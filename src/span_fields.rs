@@ -0,0 +1,206 @@
+use core::fmt;
+use std::error::Error;
+
+use tracing::{
+    field::{Field, Visit},
+    span, Subscriber,
+};
+use tracing_subscriber::{
+    field::VisitOutput, fmt::format::Writer, layer::Context, registry::LookupSpan, Layer,
+};
+
+use crate::{event_format::DevLogLayout, field_format::DevLogFieldFormat};
+
+/// Records the field values of every span, so that each output can format them with its own field
+/// settings when an event is written. The fmt layer would instead format span fields once, with
+/// the settings of whichever output saw the span first (see [`DevLogFieldFormat`]'s
+/// `FormatFields` implementation). Added once for all outputs.
+pub(crate) struct SpanFieldsLayer;
+
+/// The field values of a span, stored in the span's extensions by [`SpanFieldsLayer`].
+#[derive(Debug, Default)]
+pub(crate) struct SpanFields {
+    fields: Vec<(Field, RecordedValue)>,
+}
+
+#[derive(Debug)]
+enum RecordedValue {
+    Str(String),
+    /// A value recorded with its `Debug` implementation, already formatted.
+    Debug(String),
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    Bool(bool),
+    F64(f64),
+    Error(RecordedError),
+}
+
+/// An error and its chain of causes, formatted when recorded, since errors are only borrowed.
+#[derive(Debug)]
+struct RecordedError {
+    message: String,
+    source: Option<Box<RecordedError>>,
+}
+
+impl<SubscriberT> Layer<SubscriberT> for SpanFieldsLayer
+where
+    SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(
+        &self,
+        attributes: &span::Attributes<'_>,
+        id: &span::Id,
+        ctx: Context<'_, SubscriberT>,
+    ) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        // We record the values before locking the extensions, since a value's `Debug`
+        // implementation may panic, and the panic hook reads the extensions of active spans
+        let mut fields = SpanFields::default();
+        attributes.record(&mut fields);
+        span.extensions_mut().insert(fields);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, SubscriberT>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let mut new_fields = SpanFields::default();
+        values.record(&mut new_fields);
+
+        let mut extensions = span.extensions_mut();
+        match extensions.get_mut::<SpanFields>() {
+            Some(fields) => fields.merge(new_fields),
+            None => extensions.insert(new_fields),
+        }
+    }
+}
+
+impl SpanFields {
+    /// Whether the span has fields that are displayed with the given field format.
+    pub fn has_visible_fields(&self, field_format: &DevLogFieldFormat) -> bool {
+        self.fields
+            .iter()
+            .any(|(field, _)| !field_format.is_hidden(field.name()))
+    }
+
+    /// Writes the span's fields with the given field format, as `key=value` pairs in the compact
+    /// layout and as `key: value` pairs otherwise.
+    pub fn format(
+        &self,
+        field_format: &DevLogFieldFormat,
+        writer: Writer<'_>,
+        layout: DevLogLayout,
+    ) -> fmt::Result {
+        let mut visitor = field_format.make_span_visitor(writer, layout);
+        for (field, value) in &self.fields {
+            value.record(field, &mut visitor);
+        }
+        visitor.finish()
+    }
+
+    /// Adds fields recorded after the span was created, replacing the old values of fields that
+    /// were already recorded.
+    fn merge(&mut self, new_fields: SpanFields) {
+        for (field, value) in new_fields.fields {
+            match self
+                .fields
+                .iter_mut()
+                .find(|(existing_field, _)| *existing_field == field)
+            {
+                Some((_, existing_value)) => *existing_value = value,
+                None => self.fields.push((field, value)),
+            }
+        }
+    }
+
+    fn push(&mut self, field: &Field, value: RecordedValue) {
+        self.fields.push((field.clone(), value));
+    }
+}
+
+impl Visit for SpanFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.push(field, RecordedValue::Debug(format!("{value:?}")));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, RecordedValue::Str(value.to_string()));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, RecordedValue::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, RecordedValue::U64(value));
+    }
+
+    fn record_i128(&mut self, field: &Field, value: i128) {
+        self.push(field, RecordedValue::I128(value));
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        self.push(field, RecordedValue::U128(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, RecordedValue::Bool(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push(field, RecordedValue::F64(value));
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn Error + 'static)) {
+        self.push(field, RecordedValue::Error(RecordedError::new(value)));
+    }
+}
+
+impl RecordedValue {
+    /// Passes the value to the visitor like the span originally did.
+    fn record(&self, field: &Field, visitor: &mut dyn Visit) {
+        match self {
+            RecordedValue::Str(value) => visitor.record_str(field, value),
+            // Formatting the already formatted string with `Display` avoids quoting it again
+            RecordedValue::Debug(value) => visitor.record_debug(field, &format_args!("{value}")),
+            RecordedValue::I64(value) => visitor.record_i64(field, *value),
+            RecordedValue::U64(value) => visitor.record_u64(field, *value),
+            RecordedValue::I128(value) => visitor.record_i128(field, *value),
+            RecordedValue::U128(value) => visitor.record_u128(field, *value),
+            RecordedValue::Bool(value) => visitor.record_bool(field, *value),
+            RecordedValue::F64(value) => visitor.record_f64(field, *value),
+            RecordedValue::Error(error) => visitor.record_error(field, error),
+        }
+    }
+}
+
+impl RecordedError {
+    fn new(error: &(dyn Error + 'static)) -> Self {
+        Self {
+            message: error.to_string(),
+            source: error
+                .source()
+                .map(|source| Box::new(RecordedError::new(source))),
+        }
+    }
+}
+
+impl fmt::Display for RecordedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for RecordedError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn Error + 'static))
+    }
+}
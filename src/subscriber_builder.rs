@@ -1,25 +1,104 @@
-use std::error::Error;
+use std::{
+    env,
+    error::Error,
+    io::{self, IsTerminal},
+    mem,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
-use tracing_subscriber::fmt::{time::FormatTime, SubscriberBuilder};
+use tracing::Level;
+use tracing_core::Subscriber;
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    fmt::{self, time::FormatTime},
+    layer::{Layer, SubscriberExt},
+    registry::{LookupSpan, Registry},
+    reload,
+};
 
+#[cfg(feature = "config")]
+use crate::config_watcher::{spawn_config_watcher, ConfigFile};
 use crate::{
-    event_format::DevLogEventFormat, field_format::DevLogFieldFormat, time_format::DevLogTimeFormat,
+    event_format::{
+        DevLogEventFormat, DevLogLayout, FlightRecorderFormat, OutputFormat, TargetColoring,
+    },
+    field_format::DevLogFieldFormat,
+    flight_recorder::FlightRecorder,
+    level::{LevelLabels, LevelSet},
+    log_file::{LogFile, RollingFile},
+    output::{BoxedLayer, FilteredOutput, Output, OutputLayers},
+    panic_hook::install_panic_hook,
+    rate_limit::RateLimiter,
+    reload_handle::DevLogReloadHandle,
+    repetition::RepetitionFolder,
+    source_location::SourcePathStyle,
+    summary::{EventSummary, SummaryGuard},
+    time_format::DevLogTimeFormat,
+    writer::DevLogWriter,
 };
 
+/// The narrowest target column: an ellipsis and one character of a cut-off target.
+const MIN_TARGET_COLUMN_WIDTH: usize = 2;
+
+#[derive(Clone)]
 pub struct DevLogSubscriberBuilder<TimeFormatT> {
     field_format: DevLogFieldFormat,
     event_format: DevLogEventFormat<TimeFormatT>,
+    install_panic_hook: bool,
+    /// If `None`, colors are enabled unless the `NO_COLOR` environment variable is set.
+    pub(crate) ansi: Option<bool>,
+    #[cfg(feature = "log")]
+    install_log_bridge: bool,
+    /// Problems with configuration from the environment, which are logged as warnings once the
+    /// subscriber is initialized.
+    pub(crate) config_warnings: Vec<String>,
+    /// Whether [`DevLogSubscriberBuilder::with_env_config`] was called, so that the environment can
+    /// be applied again on top of a reloaded config file.
+    pub(crate) uses_env_config: bool,
+    /// Whether the timer was set with [`DevLogSubscriberBuilder::with_timer`] or
+    /// [`DevLogSubscriberBuilder::without_time`], in which case `DEVLOG_TIME` can't replace it.
+    custom_timer: bool,
+    writer: DevLogWriter,
+    /// Additional outputs, with their own settings.
+    outputs: Vec<Arc<dyn Output>>,
+    /// The config file that the builder was created from, if any.
+    #[cfg(feature = "config")]
+    pub(crate) config_file: Option<ConfigFile>,
 }
 
 impl Default for DevLogSubscriberBuilder<DevLogTimeFormat> {
     fn default() -> Self {
         Self {
-            field_format: DevLogFieldFormat,
+            field_format: DevLogFieldFormat::default(),
             event_format: DevLogEventFormat::default(),
+            install_panic_hook: false,
+            ansi: None,
+            #[cfg(feature = "log")]
+            install_log_bridge: true,
+            config_warnings: Vec::new(),
+            uses_env_config: false,
+            custom_timer: false,
+            writer: DevLogWriter::default(),
+            outputs: Vec::new(),
+            #[cfg(feature = "config")]
+            config_file: None,
         }
     }
 }
 
+impl DevLogSubscriberBuilder<DevLogTimeFormat> {
+    /// Switches between the devlog timers, without marking the timer as custom like
+    /// [`DevLogSubscriberBuilder::with_timer`] does.
+    #[cfg(feature = "config")]
+    pub(crate) fn with_devlog_timer(mut self, timer: DevLogTimeFormat) -> Self {
+        self.event_format.timer = timer;
+        self.event_format.env_timer = None;
+        self
+    }
+}
+
 impl<TimeFormatT> DevLogSubscriberBuilder<TimeFormatT> {
     /// Uses the given [`FormatTime`] implementation for log time formatting.
     pub fn with_timer<NewTimeFormatT: FormatTime>(
@@ -31,6 +110,10 @@ impl<TimeFormatT> DevLogSubscriberBuilder<TimeFormatT> {
             event_format: DevLogEventFormat {
                 timer,
                 // We have to set every field here for the generics to work
+                output_format: self.event_format.output_format,
+                filter: self.event_format.filter,
+                layout: self.event_format.layout,
+                wrap_lines: self.event_format.wrap_lines,
                 display_timestamp: self.event_format.display_timestamp,
                 display_target: self.event_format.display_target,
                 display_level: self.event_format.display_level,
@@ -38,31 +121,135 @@ impl<TimeFormatT> DevLogSubscriberBuilder<TimeFormatT> {
                 display_thread_name: self.event_format.display_thread_name,
                 display_filename: self.event_format.display_filename,
                 display_line_number: self.event_format.display_line_number,
+                display_spans: self.event_format.display_spans,
+                source_link_template: self.event_format.source_link_template,
+                source_path_style: self.event_format.source_path_style,
+                workspace_root: self.event_format.workspace_root,
+                target_coloring: self.event_format.target_coloring,
+                level_labels: self.event_format.level_labels,
+                pad_levels: self.event_format.pad_levels,
+                align_columns: self.event_format.align_columns,
+                target_column_width: self.event_format.target_column_width,
+                timestamp_column_width: self.event_format.timestamp_column_width,
+                repetition_folder: self.event_format.repetition_folder,
+                rate_limiter: self.event_format.rate_limiter,
+                summary: self.event_format.summary,
+                flight_recorder: self.event_format.flight_recorder,
+                flight_recorder_span_tree_only: self.event_format.flight_recorder_span_tree_only,
+                env_timer: None,
             },
+            install_panic_hook: self.install_panic_hook,
+            ansi: self.ansi,
+            #[cfg(feature = "log")]
+            install_log_bridge: self.install_log_bridge,
+            config_warnings: self.config_warnings,
+            uses_env_config: self.uses_env_config,
+            custom_timer: true,
+            writer: self.writer,
+            outputs: self.outputs,
+            #[cfg(feature = "config")]
+            config_file: self.config_file,
         }
     }
 
+    /// Replaces the timer with the given devlog timer, and shows timestamps for all levels. Returns
+    /// false without changing anything if a custom timer has been set.
+    pub(crate) fn set_env_timer(&mut self, timer: DevLogTimeFormat) -> bool {
+        if self.custom_timer {
+            return false;
+        }
+        self.event_format.env_timer = Some(timer);
+        self.event_format.display_timestamp = LevelSet::ALL;
+        true
+    }
+
     /// Excludes timestamps from log events.
     pub fn without_time(self) -> DevLogSubscriberBuilder<()> {
         DevLogSubscriberBuilder {
             field_format: self.field_format,
             event_format: DevLogEventFormat {
                 timer: (),
-                display_timestamp: false,
+                display_timestamp: LevelSet::NONE,
                 // We have to set every field here for the generics to work
+                output_format: self.event_format.output_format,
+                filter: self.event_format.filter,
+                layout: self.event_format.layout,
+                wrap_lines: self.event_format.wrap_lines,
                 display_target: self.event_format.display_target,
                 display_level: self.event_format.display_level,
                 display_thread_id: self.event_format.display_thread_id,
                 display_thread_name: self.event_format.display_thread_name,
                 display_filename: self.event_format.display_filename,
                 display_line_number: self.event_format.display_line_number,
+                display_spans: self.event_format.display_spans,
+                source_link_template: self.event_format.source_link_template,
+                source_path_style: self.event_format.source_path_style,
+                workspace_root: self.event_format.workspace_root,
+                target_coloring: self.event_format.target_coloring,
+                level_labels: self.event_format.level_labels,
+                pad_levels: self.event_format.pad_levels,
+                align_columns: self.event_format.align_columns,
+                target_column_width: self.event_format.target_column_width,
+                timestamp_column_width: self.event_format.timestamp_column_width,
+                repetition_folder: self.event_format.repetition_folder,
+                rate_limiter: self.event_format.rate_limiter,
+                summary: self.event_format.summary,
+                flight_recorder: self.event_format.flight_recorder,
+                flight_recorder_span_tree_only: self.event_format.flight_recorder_span_tree_only,
+                env_timer: None,
             },
+            install_panic_hook: self.install_panic_hook,
+            ansi: self.ansi,
+            #[cfg(feature = "log")]
+            install_log_bridge: self.install_log_bridge,
+            config_warnings: self.config_warnings,
+            uses_env_config: self.uses_env_config,
+            custom_timer: true,
+            writer: self.writer,
+            outputs: self.outputs,
+            #[cfg(feature = "config")]
+            config_file: self.config_file,
         }
     }
 
+    /// Sets how the fields and metadata of log events are laid out. See [`DevLogLayout`] for the
+    /// available layouts.
+    pub fn with_layout(mut self, layout: DevLogLayout) -> Self {
+        self.event_format.layout = layout;
+        self
+    }
+
+    /// Puts log fields and metadata on the same line as the log message. Equivalent to calling
+    /// [`DevLogSubscriberBuilder::with_layout`] with [`DevLogLayout::Compact`].
+    pub fn compact(self) -> Self {
+        self.with_layout(DevLogLayout::Compact)
+    }
+
+    /// Whether to wrap log messages and field values that are wider than the terminal. Continuation
+    /// lines are indented to the column where the message or value started, so they stay aligned
+    /// with devlog's field indentation. Only applies to events in the expanded layout, and only
+    /// when the terminal width can be queried (from the terminal that stdout is connected to, or
+    /// from the `COLUMNS` environment variable).
+    pub fn with_line_wrapping(mut self, wrap_lines: bool) -> Self {
+        self.event_format.wrap_lines = wrap_lines;
+        self
+    }
+
     /// Whether to show the target of a log event (where it originated).
-    pub fn with_target(mut self, display_target: bool) -> Self {
-        self.event_format.display_target = display_target;
+    pub fn with_target(self, display_target: bool) -> Self {
+        self.with_target_for(display_target.into())
+    }
+
+    /// Shows the target of a log event (where it originated) only for the given levels.
+    pub fn with_target_for(mut self, levels: LevelSet) -> Self {
+        self.event_format.display_target = levels;
+        self
+    }
+
+    /// Colors the targets of log events, so that logs from different modules are easy to tell apart.
+    /// See [`TargetColoring`] for the available options.
+    pub fn with_target_coloring(mut self, target_coloring: TargetColoring) -> Self {
+        self.event_format.target_coloring = target_coloring;
         self
     }
 
@@ -72,27 +259,370 @@ impl<TimeFormatT> DevLogSubscriberBuilder<TimeFormatT> {
         self
     }
 
+    /// Sets the labels that are displayed for each log level. See [`LevelLabels`] for how to set
+    /// custom labels, or use [`LevelLabels::icons`] for labels with Unicode icons.
+    pub fn with_level_labels(mut self, level_labels: LevelLabels) -> Self {
+        self.event_format.level_labels = level_labels;
+        self
+    }
+
+    /// Whether to pad level labels to the width of the widest label, so that log messages are
+    /// aligned across levels:
+    /// ```text
+    /// [17:51:18] INFO:  Server started
+    /// [17:51:19] ERROR: Database query failed
+    /// ```
+    pub fn with_padded_levels(mut self, pad_levels: bool) -> Self {
+        self.event_format.pad_levels = pad_levels;
+        self
+    }
+
+    /// Whether to lay out the timestamp and level of log events as fixed-width columns, so that log
+    /// messages always start in the same column. Level labels are padded like with
+    /// [`DevLogSubscriberBuilder::with_padded_levels`], and timestamps are padded to the width of
+    /// the widest timestamp so far. To also show the target in its own column, use
+    /// [`DevLogSubscriberBuilder::with_target_column`].
+    pub fn with_aligned_columns(mut self, align_columns: bool) -> Self {
+        self.event_format.align_columns = align_columns;
+        self
+    }
+
+    /// Shows the target of log events in a fixed-width column before the log message, instead of in
+    /// the `source` section below it. Targets wider than the column are cut off at the start, since
+    /// the end of the target is usually the most specific part. The width is at least 2, so that a
+    /// cut-off target shows at least one character after the `…`. Implies
+    /// [`DevLogSubscriberBuilder::with_aligned_columns`].
+    /// ```text
+    /// [17:51:18] INFO:  app::server  Server started
+    /// [17:51:19] ERROR: app::db      Database query failed
+    /// ```
+    pub fn with_target_column(mut self, width: usize) -> Self {
+        self.event_format.align_columns = true;
+        self.event_format.target_column_width = Some(width.max(MIN_TARGET_COLUMN_WIDTH));
+        self
+    }
+
+    /// Whether to fold consecutive log events from the same callsite with the same field values
+    /// into a single line. When a run of repeated events ends (i.e. a different event is logged,
+    /// or there have been no repeats for a second), the number of repeats is shown below the
+    /// original event:
+    /// ```text
+    /// [17:51:18] WARN: Connection refused, retrying
+    ///   (repeated 42 times over 3.1s)
+    /// [17:51:21] INFO: Connected
+    /// ```
+    /// To also report the repeat count while the run is still going on, use
+    /// [`DevLogSubscriberBuilder::with_repetition_flush_interval`].
+    pub fn with_folded_repetitions(mut self, fold_repetitions: bool) -> Self {
+        if !fold_repetitions {
+            self.event_format.repetition_folder = None;
+        } else if self.event_format.repetition_folder.is_none() {
+            self.event_format.repetition_folder = Some(RepetitionFolder::new(None));
+        }
+        self
+    }
+
+    /// Folds repeated log events like [`DevLogSubscriberBuilder::with_folded_repetitions`], but
+    /// also reports the repeat count when a repeat is logged more than `interval` after the event
+    /// was printed or the repeat count was last reported. This way, long-running retry loops still
+    /// show signs of life.
+    pub fn with_repetition_flush_interval(mut self, interval: Duration) -> Self {
+        self.event_format.repetition_folder = Some(RepetitionFolder::new(Some(interval)));
+        self
+    }
+
+    /// Limits each callsite (i.e. each log macro invocation) to at most `max_events` log events per
+    /// `period`, so that hot loops don't freeze the terminal. Events over the limit are dropped, and
+    /// the number of dropped events is reported before the next event that the callsite is allowed
+    /// to log:
+    /// ```text
+    /// (rate limit: dropped 4213 events from app::worker at src/worker.rs:42 over 1.0s)
+    /// [17:51:19] DEBUG: Processed item
+    ///   item_id: 5234
+    /// ```
+    /// If a callsite stops logging, the number of its dropped events is reported after a second.
+    ///
+    /// # Panics
+    ///
+    /// If `max_events` is 0, since that would drop every event.
+    pub fn with_rate_limit(mut self, max_events: usize, period: Duration) -> Self {
+        assert!(max_events > 0, "The rate limit must allow at least 1 event");
+        self.event_format.rate_limiter = Some(RateLimiter::new(max_events, period));
+        self
+    }
+
+    /// Sets the most verbose level of log events to display. Defaults to
+    /// [`LevelFilter::INFO`]. Targets with their own level (see
+    /// [`DevLogSubscriberBuilder::with_target_level`]) are not affected.
+    pub fn with_max_level(mut self, level: impl Into<LevelFilter>) -> Self {
+        self.event_format.filter = mem::take(&mut self.event_format.filter).with_default(level);
+        self
+    }
+
+    /// Sets the most verbose level of log events to display from the given target and its
+    /// submodules, overriding the level set by [`DevLogSubscriberBuilder::with_max_level`]. If
+    /// several configured targets match an event, the most specific one is used.
+    ///
+    /// ### Example
+    ///
+    /// ```
+    /// use tracing::level_filters::LevelFilter;
+    ///
+    /// let builder = devlog_tracing::subscriber()
+    ///     .with_target_level("app::db", LevelFilter::DEBUG)
+    ///     .with_target_level("hyper", LevelFilter::WARN);
+    /// ```
+    pub fn with_target_level(
+        mut self,
+        target: impl Into<String>,
+        level: impl Into<LevelFilter>,
+    ) -> Self {
+        self.event_format.filter =
+            mem::take(&mut self.event_format.filter).with_target(target, level);
+        self
+    }
+
+    /// Removes the levels set for the given targets with
+    /// [`DevLogSubscriberBuilder::with_target_level`].
+    #[cfg(feature = "config")]
+    pub(crate) fn without_target_levels<'a>(
+        mut self,
+        targets: impl IntoIterator<Item = &'a String>,
+    ) -> Self {
+        let removed_targets: Vec<&String> = targets.into_iter().collect();
+        let filter = mem::take(&mut self.event_format.filter);
+
+        let mut new_filter = tracing_subscriber::filter::Targets::new();
+        if let Some(default_level) = filter.default_level() {
+            new_filter = new_filter.with_default(default_level);
+        }
+        self.event_format.filter = new_filter.with_targets(
+            filter
+                .into_iter()
+                .filter(|(target, _)| !removed_targets.contains(&target)),
+        );
+        self
+    }
+
+    /// Writes log output to the given file in addition to the terminal, with colors stripped from
+    /// the file while the terminal output stays colored. The file can be rotated by size or time,
+    /// and old files cleaned up (see [`LogFile`]).
+    ///
+    /// The file is opened when the subscriber is initialized, and [`try_init`](Self::try_init)
+    /// returns an error if that fails. Later write errors are reported once to stderr.
+    ///
+    /// ### Example
+    ///
+    /// ```no_run
+    /// use devlog_tracing::{LogFile, Rotation};
+    ///
+    /// devlog_tracing::subscriber()
+    ///     .with_log_file(
+    ///         LogFile::new("logs/app.log")
+    ///             .with_rotation(Rotation::MaxSize(10 * 1024 * 1024))
+    ///             .with_max_files(5),
+    ///     )
+    ///     .init();
+    /// ```
+    pub fn with_log_file(mut self, log_file: LogFile) -> Self {
+        self.writer.log_file = Some(Arc::new(RollingFile::new(log_file)));
+        self
+    }
+
+    /// Whether to write log output to stdout. Defaults to true. Disable this to only write to a log
+    /// file (see [`DevLogSubscriberBuilder::with_log_file`]), e.g. for an additional output (see
+    /// [`DevLogSubscriberBuilder::with_output`]).
+    pub fn with_stdout(mut self, stdout: bool) -> Self {
+        self.writer.stdout = stdout;
+        self
+    }
+
+    /// Adds another output with its own settings, configured by the given builder: its own
+    /// filters, colors, destination (stdout and/or a log file) and display options. Log events
+    /// are formatted separately for each output.
+    ///
+    /// Settings that apply to the whole subscriber (the panic hook, the `log` bridge, the summary
+    /// and config file watching) are taken from this builder, and ignored on the output's
+    /// builder.
+    ///
+    /// ### Example
+    ///
+    /// Shows INFO events in the compact layout in the terminal, while capturing all events in the
+    /// expanded layout in a log file:
+    ///
+    /// ```no_run
+    /// use devlog_tracing::{DevLogLayout, LogFile};
+    /// use tracing::level_filters::LevelFilter;
+    ///
+    /// devlog_tracing::subscriber()
+    ///     .with_max_level(LevelFilter::INFO)
+    ///     .with_layout(DevLogLayout::Compact)
+    ///     .with_output(
+    ///         devlog_tracing::subscriber()
+    ///             .with_max_level(LevelFilter::TRACE)
+    ///             .with_layout(DevLogLayout::Expanded)
+    ///             .with_stdout(false)
+    ///             .with_log_file(LogFile::new("logs/trace.log")),
+    ///     )
+    ///     .init();
+    /// ```
+    pub fn with_output<OutputTimeFormatT>(
+        mut self,
+        mut output: DevLogSubscriberBuilder<OutputTimeFormatT>,
+    ) -> Self
+    where
+        OutputTimeFormatT: FormatTime + Clone + Send + Sync + 'static,
+    {
+        self.config_warnings.append(&mut output.config_warnings);
+        self.outputs.push(Arc::new(output));
+        self
+    }
+
+    /// Keeps the last `capacity` log events below the display level (see
+    /// [`DevLogSubscriberBuilder::with_max_level`]), down to the given `level`, in memory. When an
+    /// error is logged, the recorded events are shown in gray before it, to give context for the
+    /// error:
+    /// ```text
+    /// (flight recorder: 2 suppressed events before this error)
+    /// [17:51:18] DEBUG: Connecting to database
+    /// [17:51:19] DEBUG: Sending query
+    /// [17:51:19] ERROR: Database query failed
+    /// ```
+    ///
+    /// Note that recorded events are formatted even though they are not displayed, which has a
+    /// performance cost for verbose levels.
+    pub fn with_flight_recorder(mut self, capacity: usize, level: impl Into<LevelFilter>) -> Self {
+        self.event_format.flight_recorder =
+            Some(Arc::new(FlightRecorder::new(capacity, level.into())));
+        self
+    }
+
+    /// Whether the flight recorder (see [`DevLogSubscriberBuilder::with_flight_recorder`]) should
+    /// only show events from the same span tree as the error, i.e. events logged under the same
+    /// root span. Useful for servers that handle many requests concurrently. Defaults to false.
+    pub fn with_flight_recorder_span_tree_only(mut self, span_tree_only: bool) -> Self {
+        self.event_format.flight_recorder_span_tree_only = span_tree_only;
+        self
+    }
+
+    /// Whether to install a panic hook that logs panics as devlog error events (with target
+    /// `panic`), instead of printing them in Rust's default style. The event includes
+    /// the panic message, the location and thread of the panic, and the spans that were active. As
+    /// with the default panic hook, a backtrace is included if enabled by the `RUST_BACKTRACE`
+    /// environment variable. The hook is installed when initializing the subscriber.
+    /// ```text
+    /// [17:51:19] ERROR: Panicked: index out of bounds: the len is 3 but the index is 5
+    ///   location: src/server.rs:42:17
+    ///   thread: main
+    ///   source: panic
+    /// ```
+    /// If error events with target `panic` are filtered out, or the panic comes from formatting a
+    /// log event, the previously installed panic hook is called instead.
+    pub fn with_panic_hook(mut self, install_panic_hook: bool) -> Self {
+        self.install_panic_hook = install_panic_hook;
+        self
+    }
+
+    /// Whether to install a bridge from the [`log`](https://docs.rs/log) crate to `tracing` when
+    /// initializing the subscriber, so that records from dependencies that use `log` are formatted
+    /// like other log events. The target and source location of the original log record are shown,
+    /// instead of those of the bridge. Defaults to true.
+    ///
+    /// Requires the `log` cargo feature, which is enabled by default.
+    #[cfg(feature = "log")]
+    pub fn with_log_bridge(mut self, install_log_bridge: bool) -> Self {
+        self.install_log_bridge = install_log_bridge;
+        self
+    }
+
+    /// Sets the format that log events are written in. Use [`OutputFormat::Json`] for
+    /// machine-readable logs in production, while keeping the same builder setup as in development.
+    pub fn with_output_format(mut self, output_format: OutputFormat) -> Self {
+        self.event_format.output_format = output_format;
+        self.field_format.output_format = output_format;
+        self
+    }
+
+    /// Writes log events as newline-delimited JSON. Shorthand for
+    /// [`DevLogSubscriberBuilder::with_output_format`] with [`OutputFormat::Json`].
+    pub fn json(self) -> Self {
+        self.with_output_format(OutputFormat::Json)
+    }
+
+    /// Whether to use ANSI escape codes for colors (and hyperlinks) in log output. By default, colors
+    /// are enabled unless the [`NO_COLOR`](https://no-color.org/) environment variable is set.
+    pub fn with_ansi(mut self, ansi: bool) -> Self {
+        self.ansi = Some(ansi);
+        self
+    }
+
+    /// Picks the output format based on the environment: the devlog format if stdout is a terminal
+    /// or this is a debug build, and [`OutputFormat::Json`] otherwise. The `LOG_FORMAT` environment
+    /// variable overrides this, and can be set to `devlog`, `json` or `logfmt`.
+    ///
+    /// Invalid values of `LOG_FORMAT` are logged as a warning once the subscriber is initialized.
+    pub fn with_auto_output_format(mut self) -> Self {
+        let output_format = match env::var("LOG_FORMAT") {
+            Ok(value) if !value.trim().is_empty() => match OutputFormat::from_name(&value) {
+                Some(output_format) => Some(output_format),
+                None => {
+                    self.config_warnings.push(format!(
+                        "Invalid value '{value}' for LOG_FORMAT (expected 'devlog', 'json' or 'logfmt'), choosing format automatically"
+                    ));
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        let output_format = output_format.unwrap_or_else(|| {
+            if cfg!(debug_assertions) || io::stdout().is_terminal() {
+                OutputFormat::DevLog
+            } else {
+                OutputFormat::Json
+            }
+        });
+
+        self.with_output_format(output_format)
+    }
+
+    /// Writes log events in logfmt (`level=info msg="Server started" port=8000`). Shorthand for
+    /// [`DevLogSubscriberBuilder::with_output_format`] with [`OutputFormat::Logfmt`].
+    pub fn logfmt(self) -> Self {
+        self.with_output_format(OutputFormat::Logfmt)
+    }
+
     /// Whether to show the ID of the current thread in log events.
-    pub fn with_thread_ids(mut self, display_thread_id: bool) -> Self {
-        self.event_format.display_thread_id = display_thread_id;
+    pub fn with_thread_ids(self, display_thread_id: bool) -> Self {
+        self.with_thread_ids_for(display_thread_id.into())
+    }
+
+    /// Shows the ID of the current thread only in log events of the given levels.
+    pub fn with_thread_ids_for(mut self, levels: LevelSet) -> Self {
+        self.event_format.display_thread_id = levels;
         self
     }
 
     /// Whether to show the name of the current thread in log events.
-    pub fn with_thread_names(mut self, display_thread_name: bool) -> Self {
-        self.event_format.display_thread_name = display_thread_name;
+    pub fn with_thread_names(self, display_thread_name: bool) -> Self {
+        self.with_thread_names_for(display_thread_name.into())
+    }
+
+    /// Shows the name of the current thread only in log events of the given levels.
+    pub fn with_thread_names_for(mut self, levels: LevelSet) -> Self {
+        self.event_format.display_thread_name = levels;
         self
     }
 
     /// Whether to show the source code file path where a log event was logged.
     pub fn with_file(mut self, display_filename: bool) -> Self {
-        self.event_format.display_filename = display_filename;
+        self.event_format.display_filename = display_filename.into();
         self
     }
 
     /// Whether to show the line number in a source code file path where a log event was logged.
     pub fn with_line_number(mut self, display_line_number: bool) -> Self {
-        self.event_format.display_line_number = display_line_number;
+        self.event_format.display_line_number = display_line_number.into();
         self
     }
 
@@ -100,32 +630,398 @@ impl<TimeFormatT> DevLogSubscriberBuilder<TimeFormatT> {
     /// logged. Equivalent to calling [`DevLogSubscriberBuilder::with_file`] and
     /// [`DevLogSubscriberBuilder::with_line_number`] with the same value.
     pub fn with_source_location(self, display_location: bool) -> Self {
-        self.with_line_number(display_location)
-            .with_file(display_location)
+        self.with_source_location_for(display_location.into())
+    }
+
+    /// Shows the source code location (file path + line number) where a log event was logged only
+    /// for the given levels.
+    pub fn with_source_location_for(mut self, levels: LevelSet) -> Self {
+        self.event_format.display_filename = levels;
+        self.event_format.display_line_number = levels;
+        self
+    }
+
+    /// Whether to show the spans that a log event was logged in. Enabled by default.
+    pub fn with_spans(self, display_spans: bool) -> Self {
+        self.with_spans_for(display_spans.into())
+    }
+
+    /// Shows the spans that a log event was logged in only for the given levels.
+    pub fn with_spans_for(mut self, levels: LevelSet) -> Self {
+        self.event_format.display_spans = levels;
+        self
+    }
+
+    /// Shows timestamps only for log events of the given levels. To never show timestamps, use
+    /// [`DevLogSubscriberBuilder::without_time`].
+    pub fn with_timestamp_for(mut self, levels: LevelSet) -> Self {
+        self.event_format.display_timestamp = levels;
+        self
+    }
+
+    /// Sets how source file paths are displayed (see [`DevLogSubscriberBuilder::with_file`]). Paths
+    /// to dependencies can be very long, so [`SourcePathStyle::Short`] and
+    /// [`SourcePathStyle::Abbreviated`] shorten them to keep the source line readable.
+    pub fn with_source_path_style(mut self, style: SourcePathStyle) -> Self {
+        self.event_format.source_path_style = style;
+        self
+    }
+
+    /// Makes source file paths in log events (see [`DevLogSubscriberBuilder::with_file`]) into
+    /// clickable links, using [OSC 8](https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda)
+    /// terminal hyperlinks. Only applies when colors are enabled, since hyperlinks use the same
+    /// escape codes as colors. Terminals that don't support OSC 8 display the path as normal.
+    ///
+    /// The URL template can contain the following placeholders:
+    /// - `{abs_path}`: The absolute path of the source file (see
+    ///   [`DevLogSubscriberBuilder::with_workspace_root`])
+    /// - `{path}`: The path of the source file as reported by `tracing` (usually relative to the
+    ///   workspace root)
+    /// - `{line}`: The line number in the source file (1 if unknown)
+    ///
+    /// ### Examples
+    ///
+    /// - `vscode://file/{abs_path}:{line}` opens the file in VS Code
+    /// - `idea://open?file={abs_path}&line={line}` opens the file in IntelliJ IDEA / RustRover
+    /// - `file://{abs_path}` opens the file with the system's default application
+    ///
+    /// Paths are percent-encoded, so they can be used anywhere in the URL.
+    pub fn with_source_links(mut self, url_template: impl Into<String>) -> Self {
+        self.event_format.source_link_template = Some(url_template.into());
+        self
+    }
+
+    /// Sets the directory that relative source file paths are resolved against, for
+    /// `{abs_path}` in [source links](DevLogSubscriberBuilder::with_source_links), and stripped
+    /// from absolute paths by [`SourcePathStyle::Short`].
+    ///
+    /// By default, this is the root of the Cargo workspace, found from the `CARGO_MANIFEST_DIR`
+    /// environment variable that `cargo run` and `cargo test` set. When the program is not run
+    /// through Cargo, the current directory is used instead.
+    pub fn with_workspace_root(mut self, path: impl Into<PathBuf>) -> Self {
+        self.event_format.workspace_root = Some(path.into());
+        self
+    }
+
+    /// Displays event fields with the given names before all other fields (after the log message),
+    /// in the order given here. Useful for fields that you want to always appear in the same
+    /// place, such as `request_id` or `user_id`.
+    pub fn with_pinned_fields<FieldNameT: Into<String>>(
+        mut self,
+        field_names: impl IntoIterator<Item = FieldNameT>,
+    ) -> Self {
+        Arc::make_mut(&mut self.field_format.ordering).pinned_fields =
+            field_names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether to sort event fields alphabetically by name. Pinned fields (see
+    /// [`DevLogSubscriberBuilder::with_pinned_fields`]) are still displayed first. If false (the
+    /// default), fields are displayed in the order they were declared in the log macro.
+    pub fn with_sorted_fields(mut self, sort_fields: bool) -> Self {
+        Arc::make_mut(&mut self.field_format.ordering).sort_fields = sort_fields;
+        self
+    }
+
+    /// Omits fields with the given names from log events and spans.
+    pub fn with_hidden_fields<FieldNameT: Into<String>>(
+        mut self,
+        field_names: impl IntoIterator<Item = FieldNameT>,
+    ) -> Self {
+        Arc::make_mut(&mut self.field_format.ordering).hidden_fields =
+            field_names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Replaces the values of fields with the given names with `[REDACTED]` in log events and
+    /// spans, so that secrets such as passwords and tokens don't end up in the logs.
+    pub fn with_redacted_fields<FieldNameT: Into<String>>(
+        mut self,
+        field_names: impl IntoIterator<Item = FieldNameT>,
+    ) -> Self {
+        Arc::make_mut(&mut self.field_format.ordering).redacted_fields =
+            field_names.into_iter().map(Into::into).collect();
+        self
     }
 }
 
 impl<TimeFormatT> DevLogSubscriberBuilder<TimeFormatT>
 where
-    TimeFormatT: FormatTime + Send + Sync + 'static,
+    TimeFormatT: FormatTime + Clone + Send + Sync + 'static,
 {
-    pub fn finish(self) -> impl tracing::Subscriber {
-        self.build_fmt_subscriber().finish()
+    pub fn finish(self) -> impl Subscriber {
+        // We use a reloadable filter like `try_init`, since the output layers are built for that
+        // subscriber type
+        let (filter_layer, _) = reload::Layer::new(self.max_level());
+        Registry::default()
+            .with(filter_layer)
+            .with(self.build_layers())
     }
 
-    pub fn try_init(self) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
-        self.build_fmt_subscriber().try_init()
+    /// Like [`DevLogSubscriberBuilder::init`], but returns an error if a global subscriber has
+    /// already been set. If the bridge from the `log` crate can't be installed because another
+    /// `log` logger has been set, the subscriber logs a warning instead.
+    pub fn try_init(
+        mut self,
+    ) -> Result<DevLogReloadHandle<TimeFormatT>, Box<dyn Error + Send + Sync + 'static>> {
+        let config_warnings = mem::take(&mut self.config_warnings);
+        #[cfg(feature = "config")]
+        let config_file = self.config_file.take();
+        let should_install_panic_hook = self.install_panic_hook;
+        #[cfg(feature = "log")]
+        let should_install_log_bridge = self.install_log_bridge;
+
+        self.open_log_files()?;
+
+        let (filter_layer, filter_handle) = reload::Layer::new(self.max_level());
+        let (output_layers, output_handle) = reload::Layer::new(self.build_layers());
+        tracing::subscriber::set_global_default(
+            Registry::default().with(filter_layer).with(output_layers),
+        )?;
+
+        // The subscriber is already installed, so if another `log` logger has been set, we warn
+        // about it instead of returning an error and leaving the rest of the setup undone
+        #[cfg(feature = "log")]
+        let mut log_bridge_installed = false;
+        #[cfg(feature = "log")]
+        if should_install_log_bridge {
+            use tracing_log::AsLog;
+            match tracing_log::LogTracer::builder()
+                .with_max_level(LevelFilter::current().as_log())
+                .init()
+            {
+                Ok(()) => log_bridge_installed = true,
+                Err(error) => tracing::warn!(
+                    target: "devlog_tracing",
+                    "Failed to install the bridge from the log crate: {error}"
+                ),
+            }
+        }
+
+        for warning in config_warnings {
+            tracing::warn!(target: "devlog_tracing", "{warning}");
+        }
+
+        let reload_handle = DevLogReloadHandle::new(
+            self,
+            filter_handle,
+            output_handle,
+            #[cfg(feature = "log")]
+            log_bridge_installed,
+        );
+
+        if should_install_panic_hook {
+            let reload_handle = reload_handle.clone();
+            install_panic_hook(move || {
+                // If the panic comes from a reload on this thread, the new settings aren't known
+                // yet, so leave the panic to the previous hook
+                reload_handle
+                    .would_display("panic", Level::ERROR)
+                    .unwrap_or(false)
+            });
+        }
+
+        #[cfg(feature = "config")]
+        if let Some(config_file) = config_file {
+            if let Some(poll_interval) = config_file.poll_interval {
+                spawn_config_watcher(config_file, poll_interval, reload_handle.clone());
+            }
+        }
+
+        Ok(reload_handle)
+    }
+
+    /// Installs the subscriber as the global default, and returns a handle for changing its
+    /// settings at runtime (see [`DevLogReloadHandle`]).
+    ///
+    /// # Panics
+    ///
+    /// If a global subscriber has already been set.
+    pub fn init(self) -> DevLogReloadHandle<TimeFormatT> {
+        self.try_init()
+            .expect("Unable to install global subscriber")
+    }
+
+    /// Initializes the subscriber like [`DevLogSubscriberBuilder::init`], and returns a guard that
+    /// prints a summary of the warnings and errors logged during the run when dropped (or when
+    /// calling [`SummaryGuard::flush_summary`]). The summary lists the number of warnings and
+    /// errors per target, and the first occurrence of each distinct error message, so that problems
+    /// buried in scrollback are not missed:
+    /// ```text
+    /// Summary: 3 warnings, 2 errors
+    ///   ERROR    2 in app::db
+    ///   WARN     3 in app::http
+    /// First occurrence of each error:
+    ///   [17:51:19] Database query failed (x2) [app::db src/db.rs:42]
+    /// ```
+    /// The summary is written where this output's log events are written (stdout and/or the log
+    /// file), with the configured [level labels](DevLogSubscriberBuilder::with_level_labels). The
+    /// timestamp of each error is only shown if timestamps are shown for error events (see
+    /// [`DevLogSubscriberBuilder::with_timestamp_for`]).
+    ///
+    /// Keep the guard alive until the end of `main`:
+    /// ```
+    /// let _summary = devlog_tracing::subscriber().init_with_summary();
+    /// ```
+    pub fn init_with_summary(self) -> SummaryGuard {
+        self.try_init_with_summary()
+            .expect("Unable to install global subscriber")
+    }
+
+    /// Like [`DevLogSubscriberBuilder::init_with_summary`], but returns an error if a global
+    /// subscriber has already been set.
+    pub fn try_init_with_summary(
+        mut self,
+    ) -> Result<SummaryGuard, Box<dyn Error + Send + Sync + 'static>> {
+        let summary = Arc::new(EventSummary::new(
+            self.writer.clone(),
+            self.event_format.level_labels.clone(),
+        ));
+        self.event_format.summary = Some(summary.clone());
+        let repetition_folder = self.event_format.repetition_folder.clone();
+        let rate_limiter = self.event_format.rate_limiter.clone();
+        // Summaries are for short-lived runs, so we don't return a reload handle here
+        self.try_init()?;
+        Ok(SummaryGuard {
+            summary,
+            repetition_folder,
+            rate_limiter,
+        })
+    }
+
+    /// Builds the layers of this output and the additional outputs, along with the layer that
+    /// records span fields for all of them.
+    pub(crate) fn build_layers(&self) -> OutputLayers {
+        OutputLayers::new(self.build_output_layers())
+    }
+
+    /// Builds the fmt layers of this output and the additional outputs, each filtered by the
+    /// per-target levels of its output.
+    pub(crate) fn build_output_layers(&self) -> Vec<BoxedLayer> {
+        let filter = self.event_format.filter.clone();
+        let mut layers = vec![FilteredOutput::new(self.clone().build_fmt_layer(), filter).boxed()];
+        if let Some(flight_recorder_layer) = self.build_flight_recorder_layer() {
+            layers.push(flight_recorder_layer);
+        }
+        for output in &self.outputs {
+            layers.extend(output.build_output_layers());
+        }
+        layers
     }
 
-    pub fn init(self) {
-        self.build_fmt_subscriber().init()
+    /// Builds the layer that formats the events below this output's display level for its flight
+    /// recorder, if it has one.
+    fn build_flight_recorder_layer(&self) -> Option<BoxedLayer> {
+        let flight_recorder = self.event_format.flight_recorder.as_ref()?;
+        if self.event_format.output_format != OutputFormat::DevLog {
+            return None;
+        }
+
+        // The recorder gets the events and spans that it records in addition to those that the
+        // output displays, so that recorded events show the same spans as displayed ones
+        let display_filter = &self.event_format.filter;
+        let recorded_level = flight_recorder.level;
+        let recorder_filter = Targets::new()
+            .with_targets(
+                display_filter
+                    .iter()
+                    .map(|(target, level)| (target.to_string(), level.max(recorded_level))),
+            )
+            .with_default(
+                display_filter
+                    .default_level()
+                    .unwrap_or(LevelFilter::OFF)
+                    .max(recorded_level),
+            );
+
+        let mut event_format = self.event_format.clone();
+        event_format.filter = recorder_filter.clone();
+        let layer = tracing_subscriber::fmt::layer()
+            .fmt_fields(self.field_format.clone())
+            .event_format(FlightRecorderFormat(event_format))
+            .with_writer(io::sink)
+            .with_ansi(false);
+
+        Some(
+            FilteredOutput::new(layer, recorder_filter)
+                .excluding(display_filter.clone())
+                .boxed(),
+        )
     }
 
-    fn build_fmt_subscriber(
+    pub(crate) fn open_log_files(&self) -> io::Result<()> {
+        if let Some(log_file) = &self.writer.log_file {
+            log_file.open()?;
+        }
+        for output in &self.outputs {
+            output.open_log_files()?;
+        }
+        Ok(())
+    }
+
+    fn build_fmt_layer<SubscriberT>(
         self,
-    ) -> SubscriberBuilder<DevLogFieldFormat, DevLogEventFormat<TimeFormatT>> {
-        tracing_subscriber::fmt()
+    ) -> fmt::Layer<SubscriberT, DevLogFieldFormat, DevLogEventFormat<TimeFormatT>, DevLogWriter>
+    where
+        SubscriberT: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        if let Some(repetition_folder) = &self.event_format.repetition_folder {
+            repetition_folder.set_writer(self.writer.clone());
+        }
+        if let Some(rate_limiter) = &self.event_format.rate_limiter {
+            rate_limiter.set_writer(self.writer.clone());
+        }
+
+        let layer = tracing_subscriber::fmt::layer()
             .fmt_fields(self.field_format)
             .event_format(self.event_format)
+            .with_writer(self.writer.clone());
+
+        // Colors are stripped from log files anyway, so we don't add them if the output is only
+        // written to a file
+        let ansi = match self.ansi {
+            None if !self.writer.stdout => Some(false),
+            ansi => ansi,
+        };
+        match ansi {
+            Some(ansi) => layer.with_ansi(ansi),
+            None => layer,
+        }
+    }
+
+    /// Whether this output or one of the additional outputs displays events with the given target
+    /// and level.
+    pub(crate) fn would_display(&self, target: &str, level: Level) -> bool {
+        self.event_format.filter.would_enable(target, &level)
+            || self
+                .outputs
+                .iter()
+                .any(|output| output.would_display(target, level))
+    }
+
+    /// The most verbose level that any target or the flight recorder is enabled for, in this
+    /// output or the additional outputs.
+    pub(crate) fn max_level(&self) -> LevelFilter {
+        // Per-target levels are checked by each output's layer. This filter only skips events that
+        // no output enables, before they reach the layers.
+        let filter = &self.event_format.filter;
+        let max_level = filter
+            .iter()
+            .map(|(_, level)| level)
+            .chain(filter.default_level())
+            .max()
+            .unwrap_or(LevelFilter::OFF);
+
+        // Events that are recorded by the flight recorder must pass the filter, so that the
+        // recorder's layer receives them
+        let max_level = match &self.event_format.flight_recorder {
+            Some(flight_recorder) => max_level.max(flight_recorder.level),
+            None => max_level,
+        };
+
+        self.outputs
+            .iter()
+            .map(|output| output.max_level())
+            .fold(max_level, LevelFilter::max)
     }
 }
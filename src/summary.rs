@@ -0,0 +1,278 @@
+use core::fmt;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write as _},
+    io::Write as _,
+    mem,
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+use tracing::{
+    field::{Field, Visit},
+    Event, Level,
+};
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::{
+    color::{COLOR_GRAY, COLOR_RED, COLOR_RESET, COLOR_YELLOW},
+    event_format::normalized_metadata,
+    level::LevelLabels,
+    rate_limit::RateLimiter,
+    repetition::RepetitionFolder,
+    writer::DevLogWriter,
+};
+
+/// Collects warnings and errors logged during a run, so that we can print a summary of them at the
+/// end. See [`DevLogSubscriberBuilder::init_with_summary`](crate::DevLogSubscriberBuilder::init_with_summary).
+#[derive(Debug)]
+pub(crate) struct EventSummary {
+    state: Mutex<SummaryState>,
+    /// Where the log events are written, so the summary is written there too.
+    writer: DevLogWriter,
+    level_labels: LevelLabels,
+    /// Whether the log output uses colors, so the summary can match it.
+    ansi: AtomicBool,
+}
+
+#[derive(Debug, Default)]
+struct SummaryState {
+    /// The number of events per level and target.
+    counts: BTreeMap<(Level, String), usize>,
+    /// Distinct error messages, in the order they first occurred.
+    errors: Vec<ErrorOccurrence>,
+}
+
+#[derive(Debug)]
+struct ErrorOccurrence {
+    message: String,
+    count: usize,
+    timestamp: String,
+    target: String,
+    file: Option<String>,
+    line: Option<u32>,
+}
+
+impl EventSummary {
+    pub fn new(writer: DevLogWriter, level_labels: LevelLabels) -> Self {
+        Self {
+            state: Mutex::new(SummaryState::default()),
+            writer,
+            level_labels,
+            ansi: AtomicBool::new(false),
+        }
+    }
+
+    /// Records the event if it is a warning or error. `format_timestamp` is only called for the
+    /// first occurrence of each error message, and returns an empty string if the timestamp should
+    /// not be shown.
+    pub fn record(&self, event: &Event<'_>, ansi: bool, format_timestamp: impl FnOnce() -> String) {
+        let normalized_metadata = normalized_metadata(event);
+        let metadata = normalized_metadata.as_ref().unwrap_or(event.metadata());
+        let level = *metadata.level();
+        if level != Level::WARN && level != Level::ERROR {
+            return;
+        }
+
+        self.ansi.store(ansi, atomic::Ordering::Relaxed);
+
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        *state
+            .counts
+            .entry((level, metadata.target().to_string()))
+            .or_default() += 1;
+
+        if level != Level::ERROR {
+            return;
+        }
+
+        let mut visitor = MessageVisitor { message: None };
+        event.record(&mut visitor);
+        let message = visitor
+            .message
+            .unwrap_or_else(|| metadata.name().to_string());
+
+        match state
+            .errors
+            .iter_mut()
+            .find(|error| error.message == message && error.target == metadata.target())
+        {
+            Some(error) => error.count += 1,
+            None => state.errors.push(ErrorOccurrence {
+                message,
+                count: 1,
+                timestamp: format_timestamp(),
+                target: metadata.target().to_string(),
+                file: metadata.file().map(str::to_string),
+                line: metadata.line(),
+            }),
+        }
+    }
+
+    /// Writes the summary to the same writer as the log events, and resets it. Does nothing if no
+    /// warnings or errors were logged since the last flush.
+    pub fn flush(&self) {
+        let state = mem::take(&mut *self.state.lock().unwrap_or_else(PoisonError::into_inner));
+        if state.counts.is_empty() {
+            return;
+        }
+
+        let mut output = String::new();
+        // Writing to a String can't fail
+        let _ = state.write(
+            &mut output,
+            &self.level_labels,
+            self.ansi.load(atomic::Ordering::Relaxed),
+        );
+
+        // There is nowhere to report a failure to write the summary, so we ignore it
+        let _ = self.writer.make_writer().write_all(output.as_bytes());
+    }
+}
+
+impl SummaryState {
+    fn write(&self, output: &mut String, level_labels: &LevelLabels, ansi: bool) -> fmt::Result {
+        let count_level = |level: Level| -> usize {
+            self.counts
+                .iter()
+                .filter(|((count_level, _), _)| *count_level == level)
+                .map(|(_, count)| count)
+                .sum()
+        };
+        let warning_count = count_level(Level::WARN);
+        let error_count = count_level(Level::ERROR);
+
+        writeln!(output)?;
+        write_with_color(output, "Summary: ", COLOR_GRAY, ansi)?;
+        write_with_color(output, plural(warning_count, "warning"), COLOR_YELLOW, ansi)?;
+        write_with_color(output, ", ", COLOR_GRAY, ansi)?;
+        write_with_color(output, plural(error_count, "error"), COLOR_RED, ansi)?;
+        writeln!(output)?;
+
+        let label_width = level_labels
+            .warn
+            .chars()
+            .count()
+            .max(level_labels.error.chars().count());
+        for ((level, target), count) in &self.counts {
+            let (label, color) = match *level {
+                Level::ERROR => (&level_labels.error, COLOR_RED),
+                _ => (&level_labels.warn, COLOR_YELLOW),
+            };
+            write!(output, "  ")?;
+            write_with_color(output, format_args!("{label:label_width$}"), color, ansi)?;
+            writeln!(output, " {count:>4} in {target}")?;
+        }
+
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+
+        write_with_color(output, "First occurrence of each error:", COLOR_GRAY, ansi)?;
+        writeln!(output)?;
+        for error in &self.errors {
+            write!(output, "  ")?;
+            if !error.timestamp.is_empty() {
+                write_with_color(output, &error.timestamp, COLOR_GRAY, ansi)?;
+                write!(output, " ")?;
+            }
+            write!(output, "{}", error.message)?;
+            if error.count > 1 {
+                write_with_color(
+                    output,
+                    format_args!(" (x{})", error.count),
+                    COLOR_GRAY,
+                    ansi,
+                )?;
+            }
+
+            write_with_color(output, format_args!(" [{}", error.target), COLOR_GRAY, ansi)?;
+            if let Some(file) = &error.file {
+                write_with_color(output, format_args!(" {file}"), COLOR_GRAY, ansi)?;
+                if let Some(line) = error.line {
+                    write_with_color(output, format_args!(":{line}"), COLOR_GRAY, ansi)?;
+                }
+            }
+            write_with_color(output, "]", COLOR_GRAY, ansi)?;
+            writeln!(output)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Prints a summary of the warnings and errors logged during the run when dropped, or when calling
+/// [`SummaryGuard::flush_summary`]. Returned by
+/// [`DevLogSubscriberBuilder::init_with_summary`](crate::DevLogSubscriberBuilder::init_with_summary).
+#[derive(Debug)]
+#[must_use = "the summary is printed when the guard is dropped"]
+pub struct SummaryGuard {
+    pub(crate) summary: Arc<EventSummary>,
+    /// The global subscriber is never dropped, so the guard reports the last repeats of a folded
+    /// log event instead.
+    pub(crate) repetition_folder: Option<Arc<RepetitionFolder>>,
+    /// Likewise, the guard reports the events that the rate limit dropped and that have not been
+    /// reported yet.
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl SummaryGuard {
+    /// Prints the summary of warnings and errors logged so far, and resets it. Does nothing if no
+    /// warnings or errors were logged since the last flush.
+    pub fn flush_summary(&self) {
+        if let Some(repetition_folder) = &self.repetition_folder {
+            repetition_folder.flush();
+        }
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.flush();
+        }
+        self.summary.flush();
+    }
+}
+
+impl Drop for SummaryGuard {
+    fn drop(&mut self) {
+        self.flush_summary();
+    }
+}
+
+struct MessageVisitor {
+    message: Option<String>,
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = Some(format!("{value:?}"));
+        }
+    }
+}
+
+fn write_with_color(
+    output: &mut String,
+    content: impl Display,
+    color: &'static str,
+    ansi: bool,
+) -> fmt::Result {
+    if ansi {
+        write!(output, "{color}{content}{COLOR_RESET}")
+    } else {
+        write!(output, "{content}")
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
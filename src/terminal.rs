@@ -0,0 +1,183 @@
+use core::fmt;
+use std::{
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// The minimum number of columns that we fit on each line when wrapping text, even if the text is
+/// indented so far that this exceeds the terminal width.
+const MIN_WRAP_WIDTH: usize = 20;
+
+/// The terminal width to use when we fail to query it.
+pub(crate) const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+/// How long we keep using a queried terminal width before querying it again. Querying the terminal
+/// for every log event is wasteful, but we still want to pick up when the terminal is resized.
+const WIDTH_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+struct CachedWidth {
+    width: Option<usize>,
+    queried_at: Instant,
+}
+
+/// Returns the width (in columns) of the terminal that stdout is connected to. If stdout is not
+/// connected to a terminal, falls back to the `COLUMNS` environment variable. The width is cached,
+/// and refreshed at most once every [`WIDTH_REFRESH_INTERVAL`].
+pub(crate) fn terminal_width() -> Option<usize> {
+    static CACHED_WIDTH: Mutex<Option<CachedWidth>> = Mutex::new(None);
+
+    let mut cached_width = CACHED_WIDTH.lock().unwrap_or_else(PoisonError::into_inner);
+    let now = Instant::now();
+    match &*cached_width {
+        Some(cached) if now.duration_since(cached.queried_at) < WIDTH_REFRESH_INTERVAL => {
+            cached.width
+        }
+        _ => {
+            let width = query_width();
+            *cached_width = Some(CachedWidth {
+                width,
+                queried_at: now,
+            });
+            width
+        }
+    }
+}
+
+fn query_width() -> Option<usize> {
+    query_terminal_width().or_else(|| parse_columns(&std::env::var("COLUMNS").ok()?))
+}
+
+/// Parses the value of the `COLUMNS` environment variable, which shells set to the terminal width.
+fn parse_columns(columns: &str) -> Option<usize> {
+    columns.trim().parse().ok().filter(|&width| width > 0)
+}
+
+#[cfg(unix)]
+fn query_terminal_width() -> Option<usize> {
+    // SAFETY: TIOCGWINSZ only writes to the given winsize struct, which lives for the duration of
+    // the call. If stdout is not a terminal, ioctl returns an error code, which we check.
+    unsafe {
+        let mut size: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0 && size.ws_col > 0 {
+            Some(size.ws_col as usize)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn query_terminal_width() -> Option<usize> {
+    None
+}
+
+/// Writes the given text, inserting line breaks before words that would exceed `max_width`.
+/// `column` is the column that the text starts at, and continuation lines are indented by that
+/// many spaces. Words that are too long to fit on a line by themselves are split.
+pub(crate) fn write_wrapped(
+    writer: &mut impl fmt::Write,
+    text: &str,
+    column: usize,
+    max_width: usize,
+) -> fmt::Result {
+    let indent = column;
+    let max_width = max_width.max(indent + MIN_WRAP_WIDTH);
+    let mut column = column;
+
+    let write_line_break = |writer: &mut dyn fmt::Write, column: &mut usize| {
+        *column = indent;
+        write!(writer, "\n{:indent$}", "")
+    };
+
+    for (line_index, line) in text.split('\n').enumerate() {
+        // Text that already contains line breaks gets the same indentation as wrapped lines
+        if line_index != 0 {
+            write_line_break(writer, &mut column)?;
+        }
+
+        for (word_index, word) in line.split(' ').enumerate() {
+            let word_width = word.chars().count();
+
+            if word_index != 0 {
+                if column + 1 + word_width <= max_width {
+                    writer.write_char(' ')?;
+                    column += 1;
+                } else {
+                    write_line_break(writer, &mut column)?;
+                }
+            }
+
+            if column + word_width <= max_width {
+                writer.write_str(word)?;
+                column += word_width;
+                continue;
+            }
+
+            for char in word.chars() {
+                if column >= max_width {
+                    write_line_break(writer, &mut column)?;
+                }
+                writer.write_char(char)?;
+                column += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_columns, write_wrapped};
+
+    fn wrapped(text: &str, column: usize, max_width: usize) -> String {
+        let mut output = String::new();
+        write_wrapped(&mut output, text, column, max_width).unwrap();
+        output
+    }
+
+    #[test]
+    fn text_is_wrapped_before_words_that_exceed_the_width() {
+        let text = "the quick brown fox jumps over the lazy dog";
+
+        assert_eq!(
+            wrapped(text, 0, 20),
+            "the quick brown fox\njumps over the lazy\ndog"
+        );
+    }
+
+    #[test]
+    fn continuation_lines_are_indented_to_the_starting_column() {
+        let text = "the quick brown fox jumps over the lazy dog";
+
+        assert_eq!(
+            wrapped(text, 4, 30),
+            "the quick brown fox jumps\n    over the lazy dog"
+        );
+    }
+
+    #[test]
+    fn existing_line_breaks_are_indented_like_wrapped_lines() {
+        assert_eq!(wrapped("first\nsecond", 2, 80), "first\n  second");
+    }
+
+    #[test]
+    fn words_longer_than_the_width_are_split() {
+        let text = "a".repeat(25);
+
+        assert_eq!(
+            wrapped(&text, 0, 10),
+            // The width is raised to the minimum wrap width of 20
+            format!("{}\n{}", "a".repeat(20), "a".repeat(5))
+        );
+    }
+
+    #[test]
+    fn columns_variable_is_used_if_it_is_a_positive_number() {
+        assert_eq!(parse_columns("120"), Some(120));
+        assert_eq!(parse_columns(" 100\n"), Some(100));
+        assert_eq!(parse_columns("0"), None);
+        assert_eq!(parse_columns("wide"), None);
+        assert_eq!(parse_columns(""), None);
+    }
+}
@@ -1,19 +1,51 @@
 use core::fmt;
+use std::time::Instant;
 
-use chrono::Local;
+use chrono::{Local, SecondsFormat, Utc};
 use tracing_subscriber::fmt::{format::Writer, time::FormatTime};
 
-#[derive(Default)]
+/// The default timer for log events. Shows the local wall-clock time (`[17:51:18]`), or the time
+/// elapsed since the timer was created (`[12.345s]`) if created with
+/// [`DevLogTimeFormat::elapsed`].
+#[derive(Debug, Clone, Default)]
 pub struct DevLogTimeFormat {
-    // Prevents direct struct initialization, so we can add fields here later as a non-breaking
-    // change.
-    _private: (),
+    /// If set, we show the time elapsed since this instant instead of the wall-clock time. Private
+    /// to prevent direct struct initialization, so we can add fields here later as a non-breaking
+    /// change.
+    start_time: Option<Instant>,
+}
+
+impl DevLogTimeFormat {
+    /// Shows the local wall-clock time in log events, like `[17:51:18]`. This is the default.
+    pub fn clock() -> Self {
+        Self { start_time: None }
+    }
+
+    /// Shows the time elapsed since the timer was created (i.e. since the subscriber was set up),
+    /// like `[12.345s]`. Useful for seeing how long things take.
+    pub fn elapsed() -> Self {
+        Self {
+            start_time: Some(Instant::now()),
+        }
+    }
 }
 
 impl FormatTime for DevLogTimeFormat {
     fn format_time(&self, writer: &mut Writer<'_>) -> fmt::Result {
-        let time = Local::now();
-        write!(writer, "[{}]", time.format("%H:%M:%S"))?;
+        match self.start_time {
+            Some(start_time) => {
+                write!(writer, "[{:.3}s]", start_time.elapsed().as_secs_f64())?;
+            }
+            None => {
+                let time = Local::now();
+                write!(writer, "[{}]", time.format("%H:%M:%S"))?;
+            }
+        }
         Ok(())
     }
 }
+
+/// Returns the current time as an RFC 3339 timestamp in UTC, for machine-readable output formats.
+pub(crate) fn rfc3339_timestamp() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
@@ -0,0 +1,62 @@
+use std::{
+    io::{self, Write},
+    sync::Arc,
+};
+
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::log_file::RollingFile;
+
+/// Writes formatted log events to stdout (unless disabled), and to the log file if one is
+/// configured.
+#[derive(Debug, Clone)]
+pub(crate) struct DevLogWriter {
+    pub stdout: bool,
+    pub log_file: Option<Arc<RollingFile>>,
+}
+
+impl Default for DevLogWriter {
+    fn default() -> Self {
+        Self {
+            stdout: true,
+            log_file: None,
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for DevLogWriter {
+    type Writer = EventWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        EventWriter {
+            stdout: self.stdout,
+            log_file: self.log_file.as_deref(),
+        }
+    }
+}
+
+/// The fmt layer writes each log event with a single call to `write_all`, so every write here is a
+/// complete event. That lets us strip colors for the log file without splitting escape codes.
+pub(crate) struct EventWriter<'a> {
+    stdout: bool,
+    log_file: Option<&'a RollingFile>,
+}
+
+impl<'a> Write for EventWriter<'a> {
+    fn write(&mut self, event: &[u8]) -> io::Result<usize> {
+        if self.stdout {
+            io::stdout().write_all(event)?;
+        }
+        if let Some(log_file) = self.log_file {
+            log_file.write(event);
+        }
+        Ok(event.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.stdout {
+            io::stdout().flush()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use std::env;
+
+use common::capture;
+use tracing::info;
+
+fn auto_output(test_name: &str) -> String {
+    let builder = devlog_tracing::auto().without_time().with_target(false);
+    capture(test_name, builder, || info!(port = 8000, "Server started"))
+}
+
+// Environment variables are shared by the whole process, so this file must only contain one test
+#[test]
+fn auto_picks_the_output_format_from_the_environment() {
+    env::set_var("LOG_FORMAT", "logfmt");
+    assert_eq!(
+        auto_output("auto_format_logfmt"),
+        "level=info msg=\"Server started\" port=8000\n"
+    );
+
+    env::set_var("LOG_FORMAT", " JSON ");
+    assert_eq!(
+        auto_output("auto_format_json"),
+        "{\"level\":\"INFO\",\"fields\":{\"message\":\"Server started\",\"port\":8000}}\n"
+    );
+
+    // Tests are debug builds, so invalid or missing values fall back to the devlog format
+    env::set_var("LOG_FORMAT", "xml");
+    assert_eq!(
+        auto_output("auto_format_invalid"),
+        "INFO: Server started\n  port: 8000\n"
+    );
+    env::remove_var("LOG_FORMAT");
+    assert_eq!(
+        auto_output("auto_format_unset"),
+        "INFO: Server started\n  port: 8000\n"
+    );
+}
@@ -0,0 +1,93 @@
+//! Helpers for running a devlog subscriber in tests, and reading back what it wrote.
+
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use devlog_tracing::{DevLogSubscriberBuilder, LogFile};
+use tracing_subscriber::fmt::time::FormatTime;
+
+/// A directory for the log files of a test, which is emptied when created.
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// `name` must be unique among the tests, since tests run in parallel.
+    pub fn new(name: &str) -> Self {
+        let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+        if path.exists() {
+            fs::remove_dir_all(&path).expect("Failed to remove old test directory");
+        }
+        fs::create_dir_all(&path).expect("Failed to create test directory");
+        Self { path }
+    }
+
+    pub fn path(&self, file_name: &str) -> PathBuf {
+        self.path.join(file_name)
+    }
+
+    /// The names of the files in the directory, sorted.
+    pub fn file_names(&self) -> Vec<String> {
+        let mut file_names: Vec<String> = fs::read_dir(&self.path)
+            .expect("Failed to read test directory")
+            .map(|entry| {
+                let entry = entry.expect("Failed to read test directory entry");
+                entry.file_name().to_string_lossy().into_owned()
+            })
+            .collect();
+        file_names.sort();
+        file_names
+    }
+
+    pub fn read(&self, file_name: &str) -> String {
+        fs::read_to_string(self.path(file_name)).unwrap_or_default()
+    }
+}
+
+/// Runs `log` with the given builder as the default subscriber, and returns the output. The output
+/// is written to a log file instead of stdout, so that it can be read back.
+pub fn capture<TimeFormatT>(
+    test_name: &str,
+    builder: DevLogSubscriberBuilder<TimeFormatT>,
+    log: impl FnOnce(),
+) -> String
+where
+    TimeFormatT: FormatTime + Clone + Send + Sync + 'static,
+{
+    let dir = TestDir::new(test_name);
+    let subscriber = builder
+        .with_stdout(false)
+        .with_log_file(LogFile::new(dir.path("test.log")))
+        .finish();
+    // The subscriber is dropped when this returns, so anything it writes on drop is included
+    tracing::subscriber::with_default(subscriber, log);
+    dir.read("test.log")
+}
+
+/// A builder with the settings that most tests use: no timestamps (so that the output is
+/// deterministic) and no source section.
+pub fn subscriber() -> DevLogSubscriberBuilder<()> {
+    devlog_tracing::subscriber()
+        .without_time()
+        .with_target(false)
+}
+
+/// Logs a record through the `log` bridge, like a dependency that uses the `log` crate would.
+#[cfg(feature = "log")]
+pub fn log_record(target: &str, file: &str, line: u32, message: &str) {
+    use log::Log;
+
+    tracing_log::LogTracer::new().log(
+        &log::Record::builder()
+            .level(log::Level::Info)
+            .target(target)
+            .file(Some(file))
+            .line(Some(line))
+            .args(format_args!("{message}"))
+            .build(),
+    );
+}
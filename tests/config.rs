@@ -0,0 +1,124 @@
+#![cfg(feature = "config")]
+
+mod common;
+
+use std::{error::Error, fs, io};
+
+use common::{capture, TestDir};
+use devlog_tracing::{ConfigError, DevLogSubscriberBuilder, DevLogTimeFormat};
+use tracing::{debug, info, warn};
+
+fn load_config(
+    test_name: &str,
+    toml: &str,
+) -> Result<DevLogSubscriberBuilder<DevLogTimeFormat>, ConfigError> {
+    let dir = TestDir::new(test_name);
+    let config_path = dir.path("devlog.toml");
+    fs::write(&config_path, toml).unwrap();
+    DevLogSubscriberBuilder::from_config_file(&config_path)
+}
+
+fn load_error(test_name: &str, toml: &str) -> ConfigError {
+    match load_config(test_name, toml) {
+        Ok(_) => panic!("Expected the config to be rejected"),
+        Err(error) => error,
+    }
+}
+
+#[test]
+fn config_file_settings_are_applied() {
+    let builder = load_config(
+        "config_file",
+        r#"
+        level = "debug"
+        time = "none"
+        target = false
+        layout = "compact"
+
+        [fields]
+        redacted = ["password"]
+        "#,
+    )
+    .expect("Failed to load config");
+
+    let output = capture("config_file_output", builder, || {
+        debug!(user = "bob", password = "hunter2", "Logged in");
+    });
+
+    assert_eq!(output, "DEBUG: Logged in user=bob password=[REDACTED]\n");
+}
+
+#[test]
+fn devlog_section_is_read_instead_of_the_whole_file() {
+    let builder = load_config(
+        "config_devlog_section",
+        r#"
+        name = "app"
+
+        [server]
+        port = 8000
+
+        [devlog]
+        level = "warn"
+        time = "none"
+        target = false
+        "#,
+    )
+    .expect("Failed to load config");
+
+    let output = capture("config_devlog_section_output", builder, || {
+        info!("Hidden");
+        warn!("Shown");
+    });
+
+    assert_eq!(output, "WARN: Shown\n");
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    let error = load_error("config_unknown_key", "levle = \"debug\"\n");
+    assert!(error.to_string().starts_with("Invalid log config file '"));
+    let source = error.source().expect("Missing parse error").to_string();
+    assert!(source.contains("unknown field `levle`"), "{source}");
+
+    let error = load_error(
+        "config_unknown_section_key",
+        "[devlog]\nlevel = \"debug\"\n\n[devlog.theme]\nicons = true\n",
+    );
+    let source = error.source().expect("Missing parse error").to_string();
+    assert!(source.contains("unknown field `icons`"), "{source}");
+}
+
+#[test]
+fn invalid_levels_are_rejected() {
+    let error = load_error("config_invalid_level", "[filters]\nhyper = \"verbose\"\n");
+    let source = error.source().expect("Missing parse error").to_string();
+    assert!(
+        source.contains(
+            "invalid value: string \"verbose\", expected trace, debug, info, warn, error or off"
+        ),
+        "{source}"
+    );
+}
+
+#[test]
+fn missing_config_file_is_a_read_error() {
+    let dir = TestDir::new("config_missing_file");
+    let config_path = dir.path("devlog.toml");
+
+    let error = match DevLogSubscriberBuilder::from_config_file(&config_path) {
+        Ok(_) => panic!("Expected the config file to be missing"),
+        Err(error) => error,
+    };
+
+    assert_eq!(error.path(), config_path);
+    assert_eq!(
+        error.to_string(),
+        format!("Failed to read log config file '{}'", config_path.display())
+    );
+    let source = error.source().expect("Missing read error");
+    let io_error = source
+        .downcast_ref::<io::Error>()
+        .expect("Expected an IO error");
+    assert_eq!(io_error.kind(), io::ErrorKind::NotFound);
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "config")]
+
+mod common;
+
+use std::{env, fs, thread, time::Duration};
+
+use common::TestDir;
+use devlog_tracing::{DevLogSubscriberBuilder, LogFile};
+use tracing::{debug, info};
+
+// Environment variables and the global subscriber are shared by the whole process, so this file
+// must only contain one test
+#[test]
+fn reloaded_config_file_keeps_env_precedence() {
+    let dir = TestDir::new("config_watcher");
+    let config_path = dir.path("devlog.toml");
+    fs::write(&config_path, "level = \"info\"\n").unwrap();
+    env::set_var("DEVLOG_LEVEL", "debug");
+
+    DevLogSubscriberBuilder::from_config_file(&config_path)
+        .expect("Failed to load config")
+        .with_config_file_watching(Duration::from_millis(20))
+        .without_time()
+        .with_target(false)
+        .with_stdout(false)
+        .with_log_file(LogFile::new(dir.path("test.log")))
+        .with_env_config()
+        .init();
+
+    debug!("Before reload");
+
+    // The file's level is overridden by DEVLOG_LEVEL, but its layout applies
+    fs::write(&config_path, "level = \"warn\"\nlayout = \"compact\"\n").unwrap();
+    thread::sleep(Duration::from_millis(500));
+    debug!(port = 8000, "After reload");
+    info!("Info after reload");
+
+    let output = dir.read("test.log");
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "DEBUG: Before reload",
+            &format!(
+                "INFO: Reloaded log config from '{}': level: \"info\" -> \"warn\", \
+                 layout: (unset) -> \"compact\"",
+                config_path.display()
+            ),
+            "DEBUG: After reload port=8000",
+            "INFO: Info after reload",
+        ]
+    );
+}
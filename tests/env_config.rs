@@ -0,0 +1,60 @@
+mod common;
+
+use std::env;
+
+use common::{subscriber, TestDir};
+use devlog_tracing::LogFile;
+use tracing::{debug, info, trace};
+
+// Environment variables and the global subscriber are shared by the whole process, so this file
+// must only contain one test
+#[test]
+fn env_config_applies_valid_values_and_warns_about_invalid_ones() {
+    env::set_var("DEVLOG_LEVEL", "Debug");
+    env::set_var("DEVLOG_LAYOUT", "compact");
+    env::set_var("DEVLOG_SOURCE", "maybe");
+    env::set_var("DEVLOG_TARGET", " ");
+    // The test subscriber has no timer, which the environment can't replace
+    env::set_var("DEVLOG_TIME", "elapsed");
+
+    let dir = TestDir::new("env_config");
+    let reload_handle = subscriber()
+        .with_stdout(false)
+        .with_log_file(LogFile::new(dir.path("test.log")))
+        .with_env_config()
+        .init();
+
+    debug!(port = 8000, "Debug event");
+    trace!("Trace event");
+
+    let output = dir.read("test.log");
+    assert_eq!(
+        output,
+        "WARN: Ignoring value 'elapsed' for DEVLOG_TIME, since a custom timer is set\n\
+         WARN: Ignoring invalid value 'maybe' for DEVLOG_SOURCE \
+         (expected 1, 0, true, false, yes, no, on or off)\n\
+         DEBUG: Debug event port=8000\n"
+    );
+
+    env::set_var("DEVLOG_LEVEL", "loud");
+    env::set_var("DEVLOG_SOURCE", "Off");
+    env::set_var("DEVLOG_THREADS", "name,colour");
+    reload_handle
+        .modify(|builder| builder.with_env_config())
+        .expect("Failed to reload subscriber");
+    info!("After reload");
+
+    let output = dir.read("test.log");
+    let new_lines: Vec<&str> = output.lines().skip(3).collect();
+    assert_eq!(
+        new_lines,
+        [
+            "WARN: Ignoring invalid value 'loud' for DEVLOG_LEVEL \
+             (expected trace, debug, info, warn, error or off)",
+            "WARN: Ignoring value 'elapsed' for DEVLOG_TIME, since a custom timer is set",
+            "WARN: Ignoring invalid value 'name,colour' for DEVLOG_THREADS \
+             (expected name, id, name,id or none)",
+            "INFO: After reload",
+        ]
+    );
+}
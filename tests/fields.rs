@@ -0,0 +1,210 @@
+mod common;
+
+use common::{capture, subscriber, TestDir};
+use devlog_tracing::{LogFile, OutputFormat};
+use tracing::{info, info_span};
+
+#[test]
+fn fields_keep_declaration_order_by_default() {
+    let output = capture("fields_declaration_order", subscriber(), || {
+        info!(user = "bob", request_id = 7, path = "/", "Handled request");
+    });
+
+    assert_eq!(
+        output,
+        "INFO: Handled request\n  user: bob\n  request_id: 7\n  path: /\n"
+    );
+}
+
+#[test]
+fn sorted_fields_are_alphabetical() {
+    let output = capture(
+        "fields_sorted",
+        subscriber().with_sorted_fields(true),
+        || info!(user = "bob", request_id = 7, path = "/", "Handled request"),
+    );
+
+    assert_eq!(
+        output,
+        "INFO: Handled request\n  path: /\n  request_id: 7\n  user: bob\n"
+    );
+}
+
+#[test]
+fn pinned_fields_come_first_in_pinned_order() {
+    let output = capture(
+        "fields_pinned",
+        subscriber().with_pinned_fields(["request_id", "user"]),
+        || {
+            info!(
+                path = "/",
+                user = "bob",
+                method = "GET",
+                request_id = 7,
+                "Handled request"
+            )
+        },
+    );
+
+    // Fields that are not pinned keep their declaration order
+    assert_eq!(
+        output,
+        "INFO: Handled request\n  request_id: 7\n  user: bob\n  path: /\n  method: GET\n"
+    );
+}
+
+#[test]
+fn pinned_fields_come_before_sorted_fields() {
+    let output = capture(
+        "fields_pinned_and_sorted",
+        subscriber()
+            .with_pinned_fields(["user"])
+            .with_sorted_fields(true),
+        || info!(path = "/", user = "bob", method = "GET", "Handled request"),
+    );
+
+    assert_eq!(
+        output,
+        "INFO: Handled request\n  user: bob\n  method: GET\n  path: /\n"
+    );
+}
+
+#[test]
+fn pinned_fields_that_are_missing_are_skipped() {
+    let output = capture(
+        "fields_pinned_missing",
+        subscriber().with_pinned_fields(["request_id"]),
+        || info!(user = "bob", "Logged in"),
+    );
+
+    assert_eq!(output, "INFO: Logged in\n  user: bob\n");
+}
+
+#[test]
+fn sorting_applies_to_compact_layout() {
+    let output = capture(
+        "fields_sorted_compact",
+        subscriber().with_sorted_fields(true).compact(),
+        || info!(b = 2, c = 3, a = 1, "Counted"),
+    );
+
+    assert_eq!(output, "INFO: Counted a=1 b=2 c=3\n");
+}
+
+#[test]
+fn hidden_fields_are_omitted_from_events_and_spans() {
+    let output = capture(
+        "fields_hidden",
+        subscriber().with_hidden_fields(["secret"]),
+        || {
+            let _span = tracing::info_span!("request", id = 3, secret = "abc").entered();
+            info!(secret = "abc", user = "bob", "Handled request");
+        },
+    );
+
+    assert!(!output.contains("secret"), "{output}");
+    assert!(output.contains("  user: bob\n"), "{output}");
+    assert!(output.contains("- request { id: 3 }"), "{output}");
+}
+
+#[test]
+fn redacted_fields_are_replaced_in_events_and_spans() {
+    let output = capture(
+        "fields_redacted",
+        subscriber().with_redacted_fields(["password"]),
+        || {
+            let _span = tracing::info_span!("login", password = "hunter2").entered();
+            info!(password = "hunter2", user = "bob", "Logged in");
+        },
+    );
+
+    assert!(!output.contains("hunter2"), "{output}");
+    assert!(output.contains("  password: [REDACTED]\n"), "{output}");
+    assert!(
+        output.contains("- login { password: [REDACTED] }"),
+        "{output}"
+    );
+}
+
+#[test]
+fn span_fields_are_formatted_with_the_settings_of_each_output() {
+    let dir = TestDir::new("fields_redacted_mixed_outputs");
+    let output = capture(
+        "fields_redacted_mixed_outputs_main",
+        subscriber()
+            .with_output_format(OutputFormat::Json)
+            .with_output(
+                subscriber()
+                    .with_redacted_fields(["password"])
+                    .with_stdout(false)
+                    .with_log_file(LogFile::new(dir.path("redacted.log"))),
+            ),
+        || {
+            let _span = info_span!("login", user = "bob", password = "hunter2").entered();
+            info!("Logged in");
+        },
+    );
+
+    assert!(
+        output.contains(r#"{"name":"login","user":"bob","password":"hunter2"}"#),
+        "{output}"
+    );
+    let redacted_output = dir.read("redacted.log");
+    assert!(!redacted_output.contains("hunter2"), "{redacted_output}");
+    assert!(
+        redacted_output.contains("- login { user: bob, password: [REDACTED] }"),
+        "{redacted_output}"
+    );
+}
+
+#[test]
+fn span_fields_are_hidden_and_redacted_in_structured_outputs() {
+    let dir = TestDir::new("fields_redacted_structured_outputs");
+    let output = capture(
+        "fields_redacted_structured_outputs_main",
+        subscriber().with_output(
+            subscriber()
+                .with_output_format(OutputFormat::Logfmt)
+                .with_hidden_fields(["user"])
+                .with_redacted_fields(["password"])
+                .with_stdout(false)
+                .with_log_file(LogFile::new(dir.path("logfmt.log"))),
+        ),
+        || {
+            let span = info_span!("login", user = "bob", password = tracing::field::Empty);
+            let _span = span.enter();
+            span.record("password", "hunter2");
+            info!("Logged in");
+        },
+    );
+
+    assert!(
+        output.contains("- login { user: bob, password: hunter2 }"),
+        "{output}"
+    );
+    let logfmt_output = dir.read("logfmt.log");
+    assert!(!logfmt_output.contains("hunter2"), "{logfmt_output}");
+    assert!(!logfmt_output.contains("bob"), "{logfmt_output}");
+    assert!(logfmt_output.contains("[REDACTED]"), "{logfmt_output}");
+}
+
+#[test]
+fn fields_named_like_log_bridge_fields_are_shown_on_other_events() {
+    let output = capture("fields_log_prefix", subscriber(), || {
+        info!(log.level = "verbose", "Configured logging");
+    });
+
+    assert_eq!(output, "INFO: Configured logging\n  log.level: verbose\n");
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn log_bridge_fields_are_hidden_on_log_records() {
+    use common::log_record;
+
+    let output = capture("fields_log_bridge", subscriber(), || {
+        log_record("dep", "dep/src/lib.rs", 10, "From dep");
+    });
+
+    assert_eq!(output, "INFO: From dep\n");
+}
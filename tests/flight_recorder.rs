@@ -0,0 +1,63 @@
+mod common;
+
+use common::{capture, subscriber};
+use tracing::{debug, debug_span, error, info, level_filters::LevelFilter, trace};
+
+#[test]
+fn flight_recorder_shows_suppressed_events_before_errors() {
+    let builder = subscriber().with_flight_recorder(10, LevelFilter::DEBUG);
+    let output = capture("flight_recorder_errors", builder, || {
+        debug!("Connecting");
+        info!("Connected");
+        debug!("Sending query");
+        error!("Query failed");
+    });
+
+    assert_eq!(
+        output,
+        "INFO: Connected\n\
+         (flight recorder: 2 suppressed events before this error)\n\
+         DEBUG: Connecting\n\
+         DEBUG: Sending query\n\
+         ERROR: Query failed\n"
+    );
+}
+
+#[test]
+fn flight_recorder_does_not_record_events_below_its_level() {
+    // The trace level of the other target makes trace events pass the global filter
+    let builder = subscriber()
+        .with_target_level("other", LevelFilter::TRACE)
+        .with_flight_recorder(10, LevelFilter::DEBUG);
+    let output = capture("flight_recorder_level", builder, || {
+        trace!("Too verbose");
+        debug!("Recorded");
+        error!("Failed");
+    });
+
+    assert_eq!(
+        output,
+        "(flight recorder: 1 suppressed event before this error)\n\
+         DEBUG: Recorded\n\
+         ERROR: Failed\n"
+    );
+}
+
+#[test]
+fn flight_recorder_does_not_show_its_spans_in_displayed_events() {
+    let builder = subscriber().with_flight_recorder(10, LevelFilter::DEBUG);
+    let output = capture("flight_recorder_spans", builder, || {
+        let _span = debug_span!("connection").entered();
+        debug!("Connecting");
+        info!("Connected");
+        error!("Query failed");
+    });
+
+    assert_eq!(
+        output,
+        "INFO: Connected\n\
+         (flight recorder: 1 suppressed event before this error)\n\
+         DEBUG: Connecting\n  span:\n    - connection \n\
+         ERROR: Query failed\n"
+    );
+}
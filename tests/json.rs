@@ -0,0 +1,21 @@
+mod common;
+
+use common::{capture, subscriber};
+use devlog_tracing::OutputFormat;
+use tracing::{info, info_span};
+
+#[test]
+fn spans_are_nested_from_the_root_span_to_the_current_span() {
+    let builder = subscriber().with_output_format(OutputFormat::Json);
+    let output = capture("json_nested_spans", builder, || {
+        let _request = info_span!("request", id = 7).entered();
+        let _query = info_span!("query", table = "users").entered();
+        info!("Handled request");
+    });
+
+    assert_eq!(
+        output,
+        "{\"level\":\"INFO\",\"fields\":{\"message\":\"Handled request\"},\
+         \"span\":{\"name\":\"request\",\"id\":7,\"span\":{\"name\":\"query\",\"table\":\"users\"}}}\n"
+    );
+}
@@ -0,0 +1,198 @@
+mod common;
+
+use common::{capture, subscriber};
+use devlog_tracing::{DevLogLayout, LevelLabels, LevelSet};
+use tracing::{debug, error, info, info_span, level_filters::LevelFilter, warn, Level};
+
+#[test]
+fn compact_layout_puts_fields_on_one_line() {
+    let output = capture("layout_compact", subscriber().compact(), || {
+        info!(port = 8000, environment = "dev", "Server started");
+    });
+
+    assert_eq!(output, "INFO: Server started port=8000 environment=dev\n");
+}
+
+#[test]
+fn compact_layout_quotes_values_with_whitespace_or_equals_signs() {
+    let output = capture("layout_compact_quoting", subscriber().compact(), || {
+        info!(
+            alpha = "a b",
+            query = "x=1",
+            tab = "a\tb",
+            empty = "",
+            quote = "say \"hi\" now",
+            plain = "ab",
+            "Quoting"
+        );
+    });
+
+    assert_eq!(
+        output,
+        "INFO: Quoting alpha=\"a b\" query=\"x=1\" tab=\"a\\tb\" empty=\"\" quote=\"say \\\"hi\\\" now\" plain=ab\n"
+    );
+}
+
+#[test]
+fn compact_layout_quotes_debug_values_with_whitespace() {
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Point {
+        x: i32,
+    }
+
+    let output = capture("layout_compact_debug", subscriber().compact(), || {
+        info!(point = ?Point { x: 1 }, "Moved");
+    });
+
+    assert_eq!(output, "INFO: Moved point=\"Point { x: 1 }\"\n");
+}
+
+#[test]
+fn compact_layout_quotes_span_fields_like_event_fields() {
+    let output = capture("layout_compact_spans", subscriber().compact(), || {
+        let _request = info_span!("request", id = 7, path = "/a b").entered();
+        let _query = info_span!("query", table = "users").entered();
+        info!("Handled request");
+    });
+
+    assert_eq!(
+        output,
+        "INFO: Handled request span=request{id=7 path=\"/a b\"} > query{table=users}\n"
+    );
+}
+
+#[test]
+fn expanded_layout_does_not_quote_values() {
+    let output = capture("layout_expanded_no_quoting", subscriber(), || {
+        info!(alpha = "a b", "Quoting");
+    });
+
+    assert_eq!(output, "INFO: Quoting\n  alpha: a b\n");
+}
+
+#[test]
+fn adaptive_layout_keeps_short_events_on_one_line() {
+    let output = capture(
+        "layout_adaptive_short",
+        subscriber().with_layout(DevLogLayout::Adaptive),
+        || info!(port = 8000, "Server started"),
+    );
+
+    assert_eq!(output, "INFO: Server started port=8000\n");
+}
+
+#[test]
+fn adaptive_layout_expands_events_wider_than_the_terminal() {
+    // Wider than any terminal
+    let long_value = "x".repeat(1000);
+    let output = capture(
+        "layout_adaptive_long",
+        subscriber().with_layout(DevLogLayout::Adaptive),
+        || info!(port = 8000, value = long_value, "Server started"),
+    );
+
+    assert_eq!(
+        output,
+        format!("INFO: Server started\n  port: 8000\n  value: {long_value}\n")
+    );
+}
+
+#[test]
+fn adaptive_layout_expands_events_with_line_breaks() {
+    let output = capture(
+        "layout_adaptive_line_breaks",
+        subscriber().with_layout(DevLogLayout::Adaptive),
+        || info!(port = 8000, "Server started\nSecond line"),
+    );
+
+    assert_eq!(output, "INFO: Server started\nSecond line\n  port: 8000\n");
+}
+
+#[test]
+fn metadata_sections_are_only_shown_for_their_levels() {
+    let builder = subscriber()
+        .with_max_level(LevelFilter::DEBUG)
+        .with_target_for(LevelSet::at_least(Level::WARN))
+        .with_spans_for(LevelSet::at_most(Level::DEBUG));
+    let output = capture("layout_metadata_levels", builder, || {
+        let _span = info_span!("request").entered();
+        debug!("Parsing request");
+        info!("Handled request");
+        warn!("Slow request");
+    });
+
+    assert_eq!(
+        output,
+        "DEBUG: Parsing request\n  span:\n    - request \n\
+         INFO: Handled request\n\
+         WARN: Slow request\n  source: layout\n"
+    );
+}
+
+#[test]
+fn padded_level_labels_align_messages() {
+    let builder = subscriber().with_padded_levels(true);
+    let output = capture("layout_padded_levels", builder, || {
+        info!("Server started");
+        error!("Query failed");
+    });
+
+    assert_eq!(output, "INFO:  Server started\nERROR: Query failed\n");
+}
+
+#[test]
+fn icon_level_labels_are_padded_by_visible_width() {
+    let builder = subscriber()
+        .with_level_labels(LevelLabels::icons())
+        .with_padded_levels(true);
+    let output = capture("layout_icon_levels", builder, || {
+        info!("Server started");
+        warn!("Slow request");
+        error!("Query failed");
+    });
+
+    assert_eq!(
+        output,
+        "ℹ INFO:  Server started\n⚠ WARN:  Slow request\n✖ ERROR: Query failed\n"
+    );
+}
+
+#[test]
+fn custom_level_labels_replace_level_names() {
+    let builder = subscriber().with_level_labels(LevelLabels {
+        warn: String::from("WARNING"),
+        ..LevelLabels::default()
+    });
+    let output = capture("layout_custom_levels", builder, || {
+        warn!("Slow request");
+        info!("Server started");
+    });
+
+    assert_eq!(output, "WARNING: Slow request\nINFO: Server started\n");
+}
+
+#[test]
+fn aligned_columns_start_messages_in_the_same_column() {
+    let builder = subscriber().with_target(true).with_target_column(10);
+    let output = capture("layout_aligned_columns", builder, || {
+        info!(target: "app::http", "Server started");
+        error!(target: "app::db::pool", "Query failed");
+    });
+
+    assert_eq!(
+        output,
+        "INFO:  app::http  Server started\n\
+         ERROR: …:db::pool Query failed\n"
+    );
+}
+
+#[test]
+fn narrow_target_columns_show_at_least_one_character_of_the_target() {
+    let builder = subscriber().with_target(true).with_target_column(0);
+    let output = capture("layout_narrow_target_column", builder, || {
+        info!(target: "app::http", "Server started");
+    });
+
+    assert_eq!(output, "INFO:  …p Server started\n");
+}
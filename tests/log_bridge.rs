@@ -0,0 +1,40 @@
+#![cfg(feature = "log")]
+
+mod common;
+
+use common::{subscriber, TestDir};
+use devlog_tracing::LogFile;
+use tracing::info;
+
+struct OtherLogger;
+
+impl log::Log for OtherLogger {
+    fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+        false
+    }
+
+    fn log(&self, _: &log::Record<'_>) {}
+
+    fn flush(&self) {}
+}
+
+// The subscriber and the `log` logger are global, so this file must only contain one test
+#[test]
+fn subscriber_is_set_up_when_another_log_logger_is_set() {
+    log::set_logger(&OtherLogger).expect("Failed to set logger");
+
+    let dir = TestDir::new("log_bridge_other_logger");
+    subscriber()
+        .with_stdout(false)
+        .with_log_file(LogFile::new(dir.path("test.log")))
+        .try_init()
+        .expect("Failed to install subscriber");
+
+    info!("After init");
+    let output = dir.read("test.log");
+    assert!(
+        output.starts_with("WARN: Failed to install the bridge from the log crate: "),
+        "{output}"
+    );
+    assert!(output.ends_with("INFO: After init\n"), "{output}");
+}
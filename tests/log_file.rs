@@ -0,0 +1,87 @@
+mod common;
+
+use std::fs;
+
+use common::{subscriber, TestDir};
+use devlog_tracing::{LogFile, Rotation};
+use tracing::info;
+
+#[test]
+fn log_file_rotates_by_size_and_keeps_max_files() {
+    let dir = TestDir::new("log_file_rotation");
+    // Older rotated files, which are deleted first
+    for file_name in [
+        "app.2020-01-01.log",
+        "app.2020-01-01_05.log",
+        "app.2020-01-01_05-00-00-1.log",
+    ] {
+        fs::write(dir.path(file_name), "old\n").unwrap();
+    }
+    // Files that only look similar, which must be kept
+    for file_name in [
+        "app.trace.log",
+        "app.2020-01-01.bak.log",
+        "app.2020-01-01-x.log",
+    ] {
+        fs::write(dir.path(file_name), "unrelated\n").unwrap();
+    }
+
+    let log_file = LogFile::new(dir.path("app.log"))
+        .with_rotation(Rotation::MaxSize(40))
+        .with_max_files(2);
+    let subscriber = subscriber()
+        .with_stdout(false)
+        .with_log_file(log_file)
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        for index in 0..4 {
+            info!("Event number {index} with some padding");
+        }
+    });
+
+    let file_names = dir.file_names();
+    let rotated: Vec<&String> = file_names
+        .iter()
+        .filter(|name| name.starts_with("app.20") && !name.contains("2020"))
+        .collect();
+    assert_eq!(rotated.len(), 2, "{file_names:?}");
+    for file_name in [
+        "app.trace.log",
+        "app.2020-01-01.bak.log",
+        "app.2020-01-01-x.log",
+    ] {
+        assert!(
+            file_names.iter().any(|name| name == file_name),
+            "{file_names:?}"
+        );
+    }
+    assert!(!file_names
+        .iter()
+        .any(|name| name.starts_with("app.2020-01-01_05")));
+    assert!(!file_names.iter().any(|name| name == "app.2020-01-01.log"));
+
+    assert_eq!(
+        dir.read("app.log"),
+        "INFO: Event number 3 with some padding\n"
+    );
+}
+
+#[test]
+fn log_files_strip_colors_and_hyperlinks() {
+    let dir = TestDir::new("log_file_ansi");
+    let subscriber = subscriber()
+        .with_ansi(true)
+        .with_target(true)
+        .with_source_links("file://{path}")
+        .with_stdout(false)
+        .with_log_file(LogFile::new(dir.path("app.log")))
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        info!(user = "bob", "Logged in");
+    });
+
+    let output = dir.read("app.log");
+    assert!(!output.contains('\x1b'), "{output:?}");
+    assert!(output.contains("INFO: Logged in"), "{output}");
+    assert!(output.contains("  user: bob\n"), "{output}");
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{capture, subscriber, TestDir};
+use devlog_tracing::{LogFile, OutputFormat};
+use tracing::{info, level_filters::LevelFilter, trace_span};
+
+#[test]
+fn outputs_only_show_spans_that_their_own_level_enables() {
+    let dir = TestDir::new("outputs_span_levels");
+    let builder = subscriber().with_max_level(LevelFilter::INFO).with_output(
+        subscriber()
+            .with_max_level(LevelFilter::TRACE)
+            .with_stdout(false)
+            .with_log_file(LogFile::new(dir.path("trace.log"))),
+    );
+    let output = capture("outputs_span_levels_main", builder, || {
+        let _span = trace_span!("trace_only_span").entered();
+        info!("Handled request");
+    });
+
+    assert_eq!(output, "INFO: Handled request\n");
+    assert_eq!(
+        dir.read("trace.log"),
+        "INFO: Handled request\n  span:\n    - trace_only_span \n"
+    );
+}
+
+#[test]
+fn structured_outputs_only_show_spans_that_their_own_level_enables() {
+    let dir = TestDir::new("outputs_structured_span_levels");
+    let builder = subscriber()
+        .with_max_level(LevelFilter::INFO)
+        .with_output_format(OutputFormat::Json)
+        .with_output(
+            subscriber()
+                .with_max_level(LevelFilter::INFO)
+                .with_output_format(OutputFormat::Logfmt)
+                .with_stdout(false)
+                .with_log_file(LogFile::new(dir.path("logfmt.log"))),
+        )
+        .with_output(
+            subscriber()
+                .with_max_level(LevelFilter::TRACE)
+                .with_stdout(false)
+                .with_log_file(LogFile::new(dir.path("trace.log"))),
+        );
+    let output = capture("outputs_structured_span_levels_main", builder, || {
+        let _span = trace_span!("trace_only_span", id = 1).entered();
+        info!("Handled request");
+    });
+
+    assert!(!output.contains("trace_only_span"), "{output}");
+    assert!(!output.contains("\"span\""), "{output}");
+    let logfmt_output = dir.read("logfmt.log");
+    assert!(!logfmt_output.contains("span"), "{logfmt_output}");
+    let trace_output = dir.read("trace.log");
+    assert!(
+        trace_output.contains("- trace_only_span { id: 1 }"),
+        "{trace_output}"
+    );
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use std::{
+    fmt, panic,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use common::{subscriber, TestDir};
+use devlog_tracing::LogFile;
+use tracing::{info, level_filters::LevelFilter};
+
+static PREVIOUS_HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+struct PanickingDisplay;
+
+impl fmt::Display for PanickingDisplay {
+    fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+        panic!("Display panicked");
+    }
+}
+
+// The panic hook and the subscriber are global, so this file must only contain one test
+#[test]
+fn panic_hook_logs_panics_and_falls_back_to_the_previous_hook() {
+    panic::set_hook(Box::new(|_| {
+        PREVIOUS_HOOK_CALLS.fetch_add(1, Ordering::Relaxed);
+    }));
+
+    let dir = TestDir::new("panic_hook");
+    let reload_handle = subscriber()
+        .with_stdout(false)
+        .with_log_file(LogFile::new(dir.path("test.log")))
+        .with_panic_hook(true)
+        .init();
+
+    let _ = panic::catch_unwind(|| panic!("First panic"));
+    assert!(dir
+        .read("test.log")
+        .contains("ERROR: Panicked: First panic"));
+    assert_eq!(PREVIOUS_HOOK_CALLS.load(Ordering::Relaxed), 0);
+
+    // A panic while formatting an event must not be logged in the middle of that event, and the
+    // partially formatted event must not be written
+    info!(value = %PanickingDisplay, "Formatting");
+    assert!(!dir.read("test.log").contains("Display panicked"));
+    assert_eq!(PREVIOUS_HOOK_CALLS.load(Ordering::Relaxed), 1);
+    info!("After");
+    let output = dir.read("test.log");
+    assert!(!output.contains("Formatting"), "{output}");
+    assert!(output.ends_with("\nINFO: After\n"), "{output}");
+
+    // Panics that the subscriber would not display go to the previous hook
+    reload_handle
+        .modify(|builder| builder.with_target_level("panic", LevelFilter::OFF))
+        .expect("Failed to reload subscriber");
+    let _ = panic::catch_unwind(|| panic!("Filtered panic"));
+    assert!(!dir.read("test.log").contains("Filtered panic"));
+    assert_eq!(PREVIOUS_HOOK_CALLS.load(Ordering::Relaxed), 2);
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use std::{thread, time::Duration};
+
+use common::{capture, subscriber, TestDir};
+use devlog_tracing::LogFile;
+use tracing::info;
+
+#[test]
+fn events_over_the_rate_limit_are_dropped_and_reported() {
+    let builder = subscriber().with_rate_limit(2, Duration::from_millis(200));
+    let output = capture("rate_limit_dropped", builder, || {
+        let log = |item: u32| info!(item, "Processed item");
+        for item in 0..5 {
+            log(item);
+        }
+        thread::sleep(Duration::from_millis(300));
+        log(5);
+    });
+
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 7, "{output}");
+    assert_eq!(
+        lines[0..4],
+        [
+            "INFO: Processed item",
+            "  item: 0",
+            "INFO: Processed item",
+            "  item: 1"
+        ]
+    );
+    assert!(
+        lines[4]
+            .starts_with("(rate limit: dropped 3 events from rate_limit at tests/rate_limit.rs:"),
+        "{output}"
+    );
+    assert_eq!(lines[5..7], ["INFO: Processed item", "  item: 5"]);
+}
+
+#[test]
+fn callsites_are_limited_separately() {
+    let builder = subscriber().with_rate_limit(1, Duration::from_secs(60));
+    let output = capture("rate_limit_callsites", builder, || {
+        for _ in 0..2 {
+            info!("First");
+        }
+        info!("Second");
+    });
+
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 3, "{output}");
+    assert_eq!(lines[0..2], ["INFO: First", "INFO: Second"]);
+    // The dropped event is reported when the subscriber is dropped
+    assert!(
+        lines[2].starts_with("(rate limit: dropped 1 event from rate_limit at "),
+        "{output}"
+    );
+}
+
+#[test]
+fn trailing_drops_are_reported_when_the_subscriber_is_dropped() {
+    let builder = subscriber().with_rate_limit(1, Duration::from_secs(60));
+    let output = capture("rate_limit_trailing_drop", builder, || {
+        for _ in 0..3 {
+            info!("Processed item");
+        }
+    });
+
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 2, "{output}");
+    assert_eq!(lines[0], "INFO: Processed item");
+    assert!(
+        lines[1].starts_with("(rate limit: dropped 2 events from rate_limit at "),
+        "{output}"
+    );
+}
+
+#[test]
+fn trailing_drops_are_reported_after_a_quiet_period() {
+    let dir = TestDir::new("rate_limit_trailing_quiet");
+    let subscriber = subscriber()
+        .with_rate_limit(1, Duration::from_secs(60))
+        .with_stdout(false)
+        .with_log_file(LogFile::new(dir.path("test.log")))
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+        for _ in 0..3 {
+            info!("Processed item");
+        }
+        thread::sleep(Duration::from_millis(2500));
+
+        // The report is written by a timer while the subscriber is still alive
+        let output = dir.read("test.log");
+        assert!(
+            output.contains("(rate limit: dropped 2 events from rate_limit at "),
+            "{output}"
+        );
+    });
+}
+
+#[test]
+#[should_panic(expected = "The rate limit must allow at least 1 event")]
+fn rate_limit_of_zero_events_is_rejected() {
+    let _ = subscriber().with_rate_limit(0, Duration::from_secs(1));
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn log_records_are_limited_per_original_callsite() {
+    use common::log_record;
+
+    let builder = subscriber().with_rate_limit(1, Duration::from_millis(200));
+    let output = capture("rate_limit_log_records", builder, || {
+        log_record("dep_a", "dep_a/src/lib.rs", 10, "From dep_a");
+        log_record("dep_a", "dep_a/src/lib.rs", 10, "From dep_a");
+        log_record("dep_b", "dep_b/src/lib.rs", 20, "From dep_b");
+        thread::sleep(Duration::from_millis(300));
+        log_record("dep_a", "dep_a/src/lib.rs", 10, "From dep_a");
+    });
+
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 4, "{output}");
+    assert_eq!(lines[0..2], ["INFO: From dep_a", "INFO: From dep_b"]);
+    assert!(
+        lines[2]
+            .starts_with("(rate limit: dropped 1 event from dep_a at dep_a/src/lib.rs:10 over "),
+        "{output}"
+    );
+    assert_eq!(lines[3], "INFO: From dep_a");
+}
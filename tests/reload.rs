@@ -0,0 +1,37 @@
+mod common;
+
+use common::{subscriber, TestDir};
+use devlog_tracing::LogFile;
+use tracing::{debug, info, Level};
+
+// The subscriber is global, so this file must only contain one test
+#[test]
+fn modify_changes_the_level_filter_and_the_output_settings() {
+    let dir = TestDir::new("reload");
+    let reload_handle = subscriber()
+        .with_max_level(Level::INFO)
+        .with_stdout(false)
+        .with_log_file(LogFile::new(dir.path("test.log")))
+        .init();
+
+    debug!(target: "app", "Hidden before reload");
+    info!(target: "app", user = "bob", "Before reload");
+    reload_handle
+        .modify(|builder| {
+            builder
+                .with_max_level(Level::DEBUG)
+                .with_target(true)
+                .with_target_column(4)
+                .compact()
+        })
+        .expect("Failed to reload subscriber");
+    debug!(target: "app", "Shown after reload");
+    info!(target: "app", user = "bob", "After reload");
+
+    assert_eq!(
+        dir.read("test.log"),
+        "INFO: Before reload\n  user: bob\n\
+         DEBUG: app  Shown after reload\n\
+         INFO:  app  After reload user=bob\n"
+    );
+}
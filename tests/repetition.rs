@@ -0,0 +1,106 @@
+mod common;
+
+use std::{thread, time::Duration};
+
+use common::{capture, subscriber, TestDir};
+use devlog_tracing::LogFile;
+use tracing::{info, warn};
+
+#[test]
+fn repeated_events_are_folded_until_a_different_event_is_logged() {
+    let builder = subscriber().with_folded_repetitions(true);
+    let output = capture("repetition_different_event", builder, || {
+        for _ in 0..4 {
+            warn!(attempt = 1, "Connection refused");
+        }
+        info!("Connected");
+    });
+
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 4, "{output}");
+    assert_eq!(lines[0..2], ["WARN: Connection refused", "  attempt: 1"]);
+    assert!(
+        lines[2].starts_with("  (repeated 3 times over "),
+        "{output}"
+    );
+    assert_eq!(lines[3], "INFO: Connected");
+}
+
+#[test]
+fn events_with_different_field_values_are_not_folded() {
+    let builder = subscriber().with_folded_repetitions(true);
+    let output = capture("repetition_different_fields", builder, || {
+        for attempt in 0..3 {
+            warn!(attempt, "Connection refused");
+        }
+    });
+
+    assert_eq!(output.lines().count(), 3 * 2, "{output}");
+    assert!(!output.contains("repeated"), "{output}");
+}
+
+#[test]
+fn trailing_repeats_are_reported_when_the_subscriber_is_dropped() {
+    let builder = subscriber().with_folded_repetitions(true);
+    let output = capture("repetition_trailing_drop", builder, || {
+        for _ in 0..3 {
+            warn!("Connection refused");
+        }
+    });
+
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 2, "{output}");
+    assert!(
+        lines[1].starts_with("  (repeated 2 times over "),
+        "{output}"
+    );
+}
+
+#[test]
+fn trailing_repeats_are_reported_after_a_quiet_period() {
+    let dir = TestDir::new("repetition_trailing_quiet");
+    let subscriber = subscriber()
+        .with_folded_repetitions(true)
+        .with_stdout(false)
+        .with_log_file(LogFile::new(dir.path("test.log")))
+        .finish();
+
+    let log = || warn!("Connection refused");
+    tracing::subscriber::with_default(subscriber, || {
+        log();
+        log();
+        thread::sleep(Duration::from_millis(2500));
+
+        // The report is written by a timer while the subscriber is still alive
+        let output = dir.read("test.log");
+        assert!(output.contains("(repeated 1 time over "), "{output}");
+
+        // After the run is over, the event is printed again
+        log();
+        let output = dir.read("test.log");
+        assert_eq!(
+            output.matches("WARN: Connection refused").count(),
+            2,
+            "{output}"
+        );
+    });
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn log_records_from_different_callsites_are_not_folded() {
+    use common::log_record;
+
+    let builder = subscriber().with_folded_repetitions(true);
+    let output = capture("repetition_log_records", builder, || {
+        log_record("dep_a", "dep_a/src/lib.rs", 10, "Retrying");
+        log_record("dep_a", "dep_a/src/lib.rs", 10, "Retrying");
+        log_record("dep_b", "dep_b/src/lib.rs", 20, "Retrying");
+    });
+
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 3, "{output}");
+    assert_eq!(lines[0], "INFO: Retrying");
+    assert!(lines[1].starts_with("  (repeated 1 time over "), "{output}");
+    assert_eq!(lines[2], "INFO: Retrying");
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use common::{subscriber, TestDir};
+use devlog_tracing::{LevelLabels, LogFile};
+use tracing::{error, info, warn};
+
+// Installing the summary requires a global subscriber, so this file must only contain one test
+#[test]
+fn summary_is_written_to_the_configured_outputs_with_the_configured_labels() {
+    let dir = TestDir::new("summary");
+    let guard = subscriber()
+        .with_stdout(false)
+        .with_log_file(LogFile::new(dir.path("test.log")))
+        .with_level_labels(LevelLabels {
+            warn: String::from("WARNING"),
+            ..LevelLabels::default()
+        })
+        .init_with_summary();
+
+    info!("Starting");
+    warn!(target: "app::http", "Slow request");
+    error!(target: "app::db", "Query failed");
+    error!(target: "app::db", "Query failed");
+    drop(guard);
+
+    let output = dir.read("test.log");
+    let summary = &output[output.find("Summary:").expect(&output)..];
+    let lines: Vec<&str> = summary.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "Summary: 1 warning, 2 errors",
+            "  ERROR      2 in app::db",
+            "  WARNING    1 in app::http",
+            "First occurrence of each error:",
+            "  Query failed (x2) [app::db tests/summary.rs:22]",
+        ]
+    );
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use common::TestDir;
+use devlog_tracing::{LevelSet, LogFile};
+use tracing::{error, warn, Level};
+
+// Installing the summary requires a global subscriber, so this file must only contain one test
+#[test]
+fn summary_only_shows_error_timestamps_if_error_events_show_them() {
+    let dir = TestDir::new("summary_timestamps");
+    let guard = devlog_tracing::subscriber()
+        .with_target(false)
+        .with_timestamp_for(LevelSet::from_levels([Level::WARN]))
+        .with_stdout(false)
+        .with_log_file(LogFile::new(dir.path("test.log")))
+        .init_with_summary();
+
+    warn!("Slow request");
+    error!("Query failed");
+    drop(guard);
+
+    let output = dir.read("test.log");
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines[0].starts_with('['), "{output}");
+    assert_eq!(lines[1], "ERROR: Query failed", "{output}");
+    let last_line = lines.last().expect(&output);
+    assert!(
+        last_line.starts_with("  Query failed [summary_timestamps tests/summary_timestamps.rs:"),
+        "{output}"
+    );
+}
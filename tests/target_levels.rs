@@ -0,0 +1,44 @@
+mod common;
+
+use common::{capture, subscriber};
+use tracing::{info, level_filters::LevelFilter, trace, trace_span};
+
+#[test]
+fn target_levels_only_apply_to_their_targets() {
+    let builder = subscriber().with_target_level("app::db", LevelFilter::TRACE);
+    let output = capture("target_levels_events", builder, || {
+        trace!(target: "app::db", "Sending query");
+        trace!(target: "app::http", "Parsing headers");
+        info!(target: "app::http", "Handled request");
+    });
+
+    assert_eq!(output, "TRACE: Sending query\nINFO: Handled request\n");
+}
+
+#[test]
+fn spans_from_targets_below_their_level_are_not_shown() {
+    let builder = subscriber().with_target_level("app::db", LevelFilter::TRACE);
+    let output = capture("target_levels_spans", builder, || {
+        let _db_span = trace_span!(target: "app::db", "query").entered();
+        let _http_span = trace_span!(target: "app::http", "request").entered();
+        info!(target: "app::http", "Handled request");
+    });
+
+    assert_eq!(output, "INFO: Handled request\n  span:\n    - query \n");
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn log_records_are_filtered_by_their_original_target() {
+    use common::log_record;
+
+    let builder = subscriber()
+        .with_max_level(LevelFilter::WARN)
+        .with_target_level("dep_a", LevelFilter::INFO);
+    let output = capture("target_levels_log_records", builder, || {
+        log_record("dep_a", "dep_a/src/lib.rs", 10, "Shown");
+        log_record("dep_b", "dep_b/src/lib.rs", 20, "Hidden");
+    });
+
+    assert_eq!(output, "INFO: Shown\n");
+}